@@ -0,0 +1,112 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+//! Minimal checkpointed token used as the governance token in the treasury's e2e tests
+
+pub use self::checkpoint_token::{CheckpointToken, CheckpointTokenRef};
+
+/// Historical balance queries the treasury weights votes with
+#[ink::trait_definition]
+pub trait PSP22Checkpoints {
+    /// Balance of `owner` at the end of `block`
+    #[ink(message)]
+    fn balance_of_at(&self, owner: ink::H160, block: u32) -> u128;
+
+    /// Total supply at the end of `block`
+    #[ink(message)]
+    fn total_supply_at(&self, block: u32) -> u128;
+}
+
+#[ink::contract]
+mod checkpoint_token {
+    use super::PSP22Checkpoints;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use ink::H160;
+
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Error {
+        InsufficientBalance,
+    }
+
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct CheckpointToken {
+        /// Balance history of each owner as `(block, balance)`, oldest first
+        balances: Mapping<H160, Vec<(u32, Balance)>>,
+        /// Total supply history as `(block, supply)`, oldest first
+        total_supply: Vec<(u32, Balance)>,
+    }
+
+    impl CheckpointToken {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Create `amount` new tokens for the caller
+        #[ink(message)]
+        pub fn mint(&mut self, amount: Balance) {
+            let caller = self.env().caller();
+            let block = self.env().block_number();
+
+            let balance = self.balance_of_at(caller, block).saturating_add(amount);
+            self.record_balance(caller, block, balance);
+            let supply = Self::value_at(&self.total_supply, block).saturating_add(amount);
+            Self::record(&mut self.total_supply, block, supply);
+        }
+
+        /// Move `amount` of the caller's tokens to `to`
+        #[ink(message)]
+        pub fn transfer(&mut self, to: H160, amount: Balance) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let block = self.env().block_number();
+
+            let from_balance = self.balance_of_at(caller, block)
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientBalance)?;
+            self.record_balance(caller, block, from_balance);
+            let to_balance = self.balance_of_at(to, block).saturating_add(amount);
+            self.record_balance(to, block, to_balance);
+
+            Ok(())
+        }
+
+        /// Value of a history at the end of `block`
+        fn value_at(history: &[(u32, Balance)], block: u32) -> Balance {
+            history
+                .iter()
+                .rev()
+                .find(|(at, _)| *at <= block)
+                .map(|(_, value)| *value)
+                .unwrap_or(0)
+        }
+
+        /// Record the value a history has from `block` on
+        fn record(history: &mut Vec<(u32, Balance)>, block: u32, value: Balance) {
+            match history.last_mut() {
+                Some((at, last)) if *at == block => *last = value,
+                _ => history.push((block, value)),
+            }
+        }
+
+        /// Record the balance an owner has from `block` on
+        fn record_balance(&mut self, owner: H160, block: u32, balance: Balance) {
+            let mut history = self.balances.get(owner).unwrap_or_default();
+            Self::record(&mut history, block, balance);
+            self.balances.insert(owner, &history);
+        }
+    }
+
+    impl PSP22Checkpoints for CheckpointToken {
+        #[ink(message)]
+        fn balance_of_at(&self, owner: H160, block: u32) -> Balance {
+            Self::value_at(&self.balances.get(owner).unwrap_or_default(), block)
+        }
+
+        #[ink(message)]
+        fn total_supply_at(&self, block: u32) -> Balance {
+            Self::value_at(&self.total_supply, block)
+        }
+    }
+}
@@ -0,0 +1,418 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+//! Second storage version of the treasury, the upgrade target in its e2e tests
+//!
+//! The plain storage fields mirror the treasury exactly, as do proposals and votes.
+//! `DurationBounds` gains a `max_reveal_period`, which `migrate` derives from the
+//! version 1 value.
+
+pub use self::treasury_v2::{Proposal, ProposalStatus, TreasuryGovernance, TreasuryGovernanceRef, Vote};
+
+#[ink::contract]
+mod treasury_v2 {
+    use ink::prelude::string::String;
+    use ink::prelude::vec::Vec;
+    use ink::storage::traits::StorageKey;
+    use ink::storage::{Lazy, Mapping};
+    use ink::{H160, H256};
+
+    /// Version of the storage layout this code expects
+    pub const STORAGE_VERSION: u32 = 2;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ProposalType {
+        Treasury,
+        Governance,
+        Technical,
+        Other,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum VotingPeriod {
+        ThreeDays,
+        SevenDays,
+        FourteenDays,
+        ThirtyDays,
+        Custom(u32),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum QuorumThreshold {
+        Five,
+        Ten,
+        Twenty,
+        TwentyFive,
+        Custom(u16),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ExecutionDelay {
+        Immediately,
+        OneDay,
+        TwoDays,
+        SevenDays,
+        Custom(u32),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ProposalStatus {
+        Active,
+        Revealing,
+        Passed,
+        Rejected,
+        Executed,
+        Expired,
+        Cancelled,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum VotingMethod {
+        SingleChoice,
+        Quadratic { credits_per_voter: u128 },
+        RankedChoice,
+        Approval { winners: u32 },
+        CommitReveal { reveal_period: VotingPeriod },
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ApprovalThreshold {
+        SimpleMajority,
+        SixtyPercent,
+        TwoThirds,
+        Unanimous,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum OptionRole {
+        Choice,
+        For,
+        Against,
+        Abstain,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum MembershipPolicy {
+        Open,
+        RegisteredOnly,
+        OwnerApproved,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum Role {
+        Admin,
+        Guardian,
+        Member,
+        Proposer,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum RejectionReason {
+        NoQuorum,
+        Tie,
+        BelowThreshold,
+        Vetoed,
+        Cancelled,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct GovernanceParameters {
+        pub voting_period: VotingPeriod,
+        pub quorum_threshold: QuorumThreshold,
+        pub execution_delay: ExecutionDelay,
+        pub voting_method: VotingMethod,
+        pub approval_threshold: ApprovalThreshold,
+    }
+
+    /// Limits, in blocks, on the durations proposals may choose
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct DurationBounds {
+        pub min_voting_period: u32,
+        pub max_voting_period: u32,
+        pub min_execution_delay: u32,
+        pub max_execution_delay: u32,
+        /// Longest reveal period, tied to `max_voting_period` before version 2
+        pub max_reveal_period: u32,
+    }
+
+    impl Default for DurationBounds {
+        /// One to thirty days of voting, reveals and delay (6 second block time)
+        fn default() -> Self {
+            Self {
+                min_voting_period: 24 * 60 * 10,
+                max_voting_period: 30 * 24 * 60 * 10,
+                min_execution_delay: 0,
+                max_execution_delay: 30 * 24 * 60 * 10,
+                max_reveal_period: 30 * 24 * 60 * 10,
+            }
+        }
+    }
+
+    /// `DurationBounds` as version 1 stored them, still embedded in stored proposals
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct DurationBoundsV1 {
+        pub min_voting_period: u32,
+        pub max_voting_period: u32,
+        pub min_execution_delay: u32,
+        pub max_execution_delay: u32,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct QuorumLimits {
+        pub min_basis_points: u16,
+        pub max_basis_points: u16,
+        pub min_votes: u128,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct VotingOptions {
+        pub options: Vec<String>,
+        pub roles: Vec<OptionRole>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct VoteChoice {
+        pub option_index: u32,
+        pub option_text: String,
+        pub weight: u128,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TreasurySpend {
+        pub beneficiary: H160,
+        pub amount: Balance,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ProposalAction {
+        pub callee: H160,
+        pub selector: [u8; 4],
+        pub input: Vec<u8>,
+        pub transferred_value: Balance,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ProposalPayload {
+        Signal,
+        Spend(TreasurySpend),
+        Calls(Vec<ProposalAction>),
+        Configure(ConfigChange),
+        Upgrade(H256),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TypePolicy {
+        pub min_voting_period: u32,
+        pub min_quorum_basis_points: u16,
+        pub min_execution_delay: u32,
+        pub allowed_proposers: Vec<Role>,
+    }
+
+    /// Configuration changes as version 1 stored them
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ConfigChange {
+        SetTypePolicy(ProposalType, TypePolicy),
+        SetDefaultParameters(GovernanceParameters),
+        SetMembershipPolicy(MembershipPolicy),
+        SetOwner(AccountId),
+        SetGuardian(Option<AccountId>),
+        SetDurationBounds(DurationBoundsV1),
+        SetQuorumLimits(QuorumLimits),
+        SetExecutionGracePeriod(u32),
+    }
+
+    /// A proposal as version 1 stored it, unchanged by the migration
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Proposal {
+        pub id: u32,
+        pub title: String,
+        pub description: String,
+        pub proposal_type: ProposalType,
+        pub governance_params: GovernanceParameters,
+        pub voting_options: VotingOptions,
+        pub proposer: AccountId,
+        pub created_at: u32,
+        pub voting_end: u32,
+        pub reveal_end: u32,
+        pub execution_time: u32,
+        pub status: ProposalStatus,
+        pub vote_counts: Vec<u128>,
+        pub total_voters: u32,
+        pub turnout: u128,
+        pub winning_options: Vec<u32>,
+        pub rejection_reason: Option<RejectionReason>,
+        pub payload: ProposalPayload,
+        pub electorate_size: u32,
+        pub total_voting_power: u128,
+        pub snapshot_block: u32,
+        pub min_quorum_votes: u128,
+        pub execution_grace_period: u32,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Vote {
+        pub voter: AccountId,
+        pub choices: Vec<VoteChoice>,
+        pub timestamp: u32,
+        pub weight: u128,
+        pub credits_spent: u128,
+        pub delegators: Vec<AccountId>,
+    }
+
+    #[ink(storage)]
+    pub struct TreasuryGovernance {
+        next_proposal_id: u32,
+        proposals: Mapping<u32, Proposal>,
+        votes: Mapping<(u32, AccountId), Vote>,
+        proposal_ids: Vec<u32>,
+        total_voters: u32,
+        owner: AccountId,
+        membership_policy: MembershipPolicy,
+        total_deposited: Balance,
+        total_paid_out: Balance,
+        governance_token: Option<H160>,
+        guardian: Option<AccountId>,
+        execution_grace_period: u32,
+        duration_bounds: Lazy<DurationBounds>,
+        storage_version: u32,
+    }
+
+    impl TreasuryGovernance {
+        /// Constructor for a fresh deployment, owned by the caller
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            let mut bytes = [0u8; 32];
+            bytes[12..32].copy_from_slice(Self::env().caller().as_ref());
+
+            let mut contract = Self {
+                next_proposal_id: 1,
+                proposals: Mapping::default(),
+                votes: Mapping::default(),
+                proposal_ids: Vec::new(),
+                total_voters: 0,
+                owner: AccountId::from(bytes),
+                membership_policy: MembershipPolicy::Open,
+                total_deposited: 0,
+                total_paid_out: 0,
+                governance_token: None,
+                guardian: None,
+                execution_grace_period: 14 * 24 * 60 * 10,
+                duration_bounds: Lazy::new(),
+                storage_version: STORAGE_VERSION,
+            };
+            contract.duration_bounds.set(&DurationBounds::default());
+            contract
+        }
+
+        /// Bring version 1 storage up to `STORAGE_VERSION`
+        ///
+        /// Only the `duration_bounds` cell is rewritten. `DurationBounds` values embedded in
+        /// stored proposals, as `ProposalPayload::Configure(ConfigChange::SetDurationBounds)`,
+        /// are not migrated: they keep the version 1 layout, which is why `ConfigChange`
+        /// still holds a `DurationBoundsV1`.
+        #[ink(message)]
+        pub fn migrate(&mut self) {
+            if self.storage_version >= STORAGE_VERSION {
+                return;
+            }
+
+            // Version 1 held reveal periods to the voting period limit
+            let old: Option<DurationBoundsV1> =
+                ink::env::get_contract_storage(&self.duration_bounds.key()).unwrap_or_default();
+            if let Some(old) = old {
+                self.duration_bounds.set(&DurationBounds {
+                    min_voting_period: old.min_voting_period,
+                    max_voting_period: old.max_voting_period,
+                    min_execution_delay: old.min_execution_delay,
+                    max_execution_delay: old.max_execution_delay,
+                    max_reveal_period: old.max_voting_period,
+                });
+            }
+
+            self.storage_version = STORAGE_VERSION;
+        }
+
+        /// Get the layout version of the data in storage
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u32 {
+            self.storage_version
+        }
+
+        /// Get total registered voters
+        #[ink(message)]
+        pub fn get_total_voters(&self) -> u32 {
+            self.total_voters
+        }
+
+        /// Get all proposal IDs
+        #[ink(message)]
+        pub fn get_all_proposal_ids(&self) -> Vec<u32> {
+            self.proposal_ids.clone()
+        }
+
+        /// Get proposal details as stored
+        #[ink(message)]
+        pub fn get_proposal(&self, proposal_id: u32) -> Option<Proposal> {
+            self.proposals.get(proposal_id)
+        }
+
+        /// Get user's vote on a proposal
+        #[ink(message)]
+        pub fn get_user_vote(&self, proposal_id: u32, user: AccountId) -> Option<Vote> {
+            self.votes.get((proposal_id, user))
+        }
+
+        /// Get the limits proposal durations are validated against
+        #[ink(message)]
+        pub fn get_duration_bounds(&self) -> DurationBounds {
+            self.duration_bounds.get().unwrap_or_default()
+        }
+    }
+}
@@ -3,10 +3,28 @@
 
 #[ink::contract]
 mod treasury_governance {
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::hash::Keccak256;
     use ink::prelude::string::String;
     use ink::prelude::vec;
     use ink::prelude::vec::Vec;
-    use ink::storage::Mapping;
+    use ink::storage::{Lazy, Mapping};
+    use ink::{H160, H256, U256};
+
+    /// Maximum number of cross-contract calls a single proposal may carry
+    pub const MAX_PROPOSAL_ACTIONS: usize = 10;
+
+    /// Maximum length of a delegation chain, longer chains are neither created nor followed
+    pub const MAX_DELEGATION_DEPTH: u32 = 5;
+
+    /// Maximum number of accounts delegating to one account, directly or through others
+    pub const MAX_DELEGATION_SUBTREE: u32 = 128;
+
+    /// Blocks a passed proposal stays executable after its execution time, unless reconfigured (14 days)
+    pub const DEFAULT_EXECUTION_GRACE_PERIOD: u32 = 14 * 24 * 60 * 10;
+
+    /// Version of the storage layout this code expects, bumped whenever a migration is added
+    pub const STORAGE_VERSION: u32 = 1;
 
     // ========== ENUMS ==========
 
@@ -28,6 +46,8 @@ mod treasury_governance {
         SevenDays,
         FourteenDays,
         ThirtyDays,
+        /// An explicit number of blocks
+        Custom(u32),
     }
 
     impl VotingPeriod {
@@ -38,6 +58,7 @@ mod treasury_governance {
                 VotingPeriod::SevenDays => 7 * 24 * 60 * 10,      // 100,800 blocks
                 VotingPeriod::FourteenDays => 14 * 24 * 60 * 10,  // 201,600 blocks
                 VotingPeriod::ThirtyDays => 30 * 24 * 60 * 10,    // 432,000 blocks
+                VotingPeriod::Custom(blocks) => *blocks,
             }
         }
     }
@@ -50,16 +71,24 @@ mod treasury_governance {
         Ten,
         Twenty,
         TwentyFive,
+        /// An explicit quorum in basis points (1/100 of a percent)
+        Custom(u16),
     }
 
     impl QuorumThreshold {
-        /// Get percentage value
+        /// Get percentage value, rounded down for custom basis points
         pub fn to_percentage(&self) -> u32 {
+            (self.to_basis_points() / 100) as u32
+        }
+
+        /// Get the quorum in basis points
+        pub fn to_basis_points(&self) -> u16 {
             match self {
-                QuorumThreshold::Five => 5,
-                QuorumThreshold::Ten => 10,
-                QuorumThreshold::Twenty => 20,
-                QuorumThreshold::TwentyFive => 25,
+                QuorumThreshold::Five => 500,
+                QuorumThreshold::Ten => 1_000,
+                QuorumThreshold::Twenty => 2_000,
+                QuorumThreshold::TwentyFive => 2_500,
+                QuorumThreshold::Custom(basis_points) => *basis_points,
             }
         }
     }
@@ -72,6 +101,8 @@ mod treasury_governance {
         OneDay,
         TwoDays,
         SevenDays,
+        /// An explicit number of blocks
+        Custom(u32),
     }
 
     impl ExecutionDelay {
@@ -82,6 +113,7 @@ mod treasury_governance {
                 ExecutionDelay::OneDay => 24 * 60 * 10,      // 14,400 blocks
                 ExecutionDelay::TwoDays => 2 * 24 * 60 * 10, // 28,800 blocks
                 ExecutionDelay::SevenDays => 7 * 24 * 60 * 10, // 100,800 blocks
+                ExecutionDelay::Custom(blocks) => *blocks,
             }
         }
     }
@@ -91,10 +123,124 @@ mod treasury_governance {
     #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
     pub enum ProposalStatus {
         Active,
+        /// Commit-reveal voting has closed and committed votes are being revealed
+        Revealing,
         Passed,
         Rejected,
         Executed,
+        /// Passed but not executed within the execution grace period
         Expired,
+        /// Withdrawn by the proposer or stopped by the owner or guardian
+        Cancelled,
+    }
+
+    /// How ballots are cast and weighted
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum VotingMethod {
+        /// One option per ballot, carrying the voter's full weight
+        SingleChoice,
+        /// Voice credits spread over options, each option receiving the square root of its credits
+        Quadratic { credits_per_voter: u128 },
+        /// Ranked ballots decided by instant runoff
+        RankedChoice,
+        /// Ballots approve any subset of options; the `winners` most-approved options pass
+        Approval { winners: u32 },
+        /// Single-choice ballots committed as hashes during voting and revealed afterwards
+        CommitReveal { reveal_period: VotingPeriod },
+    }
+
+    /// Share of the cast votes the winning option must reach for a proposal to pass
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ApprovalThreshold {
+        /// More than half
+        SimpleMajority,
+        /// At least 60%
+        SixtyPercent,
+        /// At least two thirds
+        TwoThirds,
+        /// Every cast vote
+        Unanimous,
+    }
+
+    impl ApprovalThreshold {
+        /// Check whether `votes` out of `cast` clears the threshold
+        pub fn is_met(&self, votes: u128, cast: u128) -> bool {
+            if votes == 0 {
+                return false;
+            }
+            match self {
+                ApprovalThreshold::SimpleMajority => votes.saturating_mul(2) > cast,
+                ApprovalThreshold::SixtyPercent => votes.saturating_mul(5) >= cast.saturating_mul(3),
+                ApprovalThreshold::TwoThirds => votes.saturating_mul(3) >= cast.saturating_mul(2),
+                ApprovalThreshold::Unanimous => votes >= cast,
+            }
+        }
+    }
+
+    /// What a voting option stands for when the tally is computed
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum OptionRole {
+        /// An ordinary option that can win
+        Choice,
+        /// Support on a binary proposal
+        For,
+        /// Opposition on a binary proposal
+        Against,
+        /// Counts toward quorum but can neither win nor count toward the approval threshold
+        ///
+        /// Not accepted on ranked-choice or approval proposals.
+        Abstain,
+    }
+
+    /// Who may vote and create proposals
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum MembershipPolicy {
+        /// Any account may vote and create proposals
+        Open,
+        /// Accounts must call `register_voter` first
+        RegisteredOnly,
+        /// `register_voter` files an application the owner must approve
+        OwnerApproved,
+    }
+
+    /// Capacities an account can act in
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum Role {
+        /// Configures the contract and manages roles; always held by the owner
+        Admin,
+        /// Cancels and vetoes proposals; always held by the guardian
+        Guardian,
+        /// Always held by registered voters, and only by them; it cannot be granted
+        Member,
+        /// Creates proposals without being a member, and can be required by type policies
+        Proposer,
+    }
+
+    /// Why a proposal was rejected, cancelled or vetoed
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum RejectionReason {
+        /// Turnout stayed below the quorum
+        NoQuorum,
+        /// No single option (or set of top options) came out ahead
+        Tie,
+        /// The winning option did not reach the approval threshold
+        BelowThreshold,
+        /// The owner or guardian cancelled the proposal after it passed
+        Vetoed,
+        /// The proposal was withdrawn or cancelled before it passed
+        Cancelled,
     }
 
     // ========== STRUCTS ==========
@@ -106,6 +252,84 @@ mod treasury_governance {
         pub voting_period: VotingPeriod,
         pub quorum_threshold: QuorumThreshold,
         pub execution_delay: ExecutionDelay,
+        pub voting_method: VotingMethod,
+        pub approval_threshold: ApprovalThreshold,
+    }
+
+    impl Default for GovernanceParameters {
+        /// Seven days of single-choice voting, 10% quorum, simple majority and a one day delay
+        fn default() -> Self {
+            Self {
+                voting_period: VotingPeriod::SevenDays,
+                quorum_threshold: QuorumThreshold::Ten,
+                execution_delay: ExecutionDelay::OneDay,
+                voting_method: VotingMethod::SingleChoice,
+                approval_threshold: ApprovalThreshold::SimpleMajority,
+            }
+        }
+    }
+
+    /// Limits, in blocks, on the durations proposals may choose
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct DurationBounds {
+        /// Shortest voting period, also applied to reveal periods
+        pub min_voting_period: u32,
+        /// Longest voting period, also applied to reveal periods
+        pub max_voting_period: u32,
+        pub min_execution_delay: u32,
+        pub max_execution_delay: u32,
+    }
+
+    impl Default for DurationBounds {
+        /// One to thirty days of voting and at most thirty days of delay (6 second block time)
+        fn default() -> Self {
+            Self {
+                min_voting_period: 24 * 60 * 10,
+                max_voting_period: 30 * 24 * 60 * 10,
+                min_execution_delay: 0,
+                max_execution_delay: 30 * 24 * 60 * 10,
+            }
+        }
+    }
+
+    impl DurationBounds {
+        /// Check that every minimum is at most its maximum and voting cannot be instant
+        pub fn is_valid(&self) -> bool {
+            self.min_voting_period > 0
+                && self.min_voting_period <= self.max_voting_period
+                && self.min_execution_delay <= self.max_execution_delay
+        }
+    }
+
+    /// Limits on the quorum proposals may choose
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct QuorumLimits {
+        pub min_basis_points: u16,
+        pub max_basis_points: u16,
+        /// Votes every proposal needs regardless of its quorum percentage
+        pub min_votes: u128,
+    }
+
+    impl Default for QuorumLimits {
+        /// Any quorum from 1% to 100%, with no absolute floor
+        fn default() -> Self {
+            Self {
+                min_basis_points: 100,
+                max_basis_points: 10_000,
+                min_votes: 0,
+            }
+        }
+    }
+
+    impl QuorumLimits {
+        /// Check that the range is ordered and does not exceed 100%
+        pub fn is_valid(&self) -> bool {
+            self.min_basis_points <= self.max_basis_points && self.max_basis_points <= 10_000
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Eq)]
@@ -113,6 +337,25 @@ mod treasury_governance {
     #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
     pub struct VotingOptions {
         pub options: Vec<String>,
+        /// Role of each option, empty when every option is an ordinary `Choice`
+        pub roles: Vec<OptionRole>,
+    }
+
+    impl VotingOptions {
+        /// Whether the option at `index` is designated as abstain
+        pub fn is_abstain(&self, index: usize) -> bool {
+            self.roles.get(index) == Some(&OptionRole::Abstain)
+        }
+
+        /// Index of the first option carrying `role`
+        pub fn position(&self, role: OptionRole) -> Option<usize> {
+            self.roles.iter().position(|r| *r == role)
+        }
+
+        /// Whether options are designated for/against, making this a binary proposal
+        pub fn is_binary(&self) -> bool {
+            self.position(OptionRole::For).is_some() || self.position(OptionRole::Against).is_some()
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Eq)]
@@ -121,6 +364,137 @@ mod treasury_governance {
     pub struct VoteChoice {
         pub option_index: u32,
         pub option_text: String,
+        /// Weight added to this option's vote count
+        pub weight: u128,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct RankedBallot {
+        pub voter: AccountId,
+        /// Option indices in order of preference
+        pub ranking: Vec<u32>,
+        pub weight: u128,
+    }
+
+    /// One round of an instant-runoff tally
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct RunoffRound {
+        /// Weight counted for each option in this round
+        pub tallies: Vec<u128>,
+        /// Option eliminated at the end of the round, `None` in the final round
+        pub eliminated: Option<u32>,
+    }
+
+    /// A single-choice vote signed off-chain and submitted by a relayer
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct SignedBallot {
+        pub voter: H160,
+        pub proposal_id: u32,
+        pub option_index: u32,
+        pub nonce: u64,
+        /// Recoverable secp256k1 signature over `signed_vote_digest`
+        pub signature: [u8; 65],
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TreasurySpend {
+        pub beneficiary: H160,
+        pub amount: Balance,
+    }
+
+    /// A cross-contract call dispatched when a proposal is executed
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ProposalAction {
+        pub callee: H160,
+        pub selector: [u8; 4],
+        /// SCALE-encoded message arguments, appended verbatim after the selector
+        pub input: Vec<u8>,
+        pub transferred_value: Balance,
+    }
+
+    /// Outcome of a dispatched `ProposalAction`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct ActionResult {
+        pub success: bool,
+        /// Raw return data of the call, empty if it failed
+        pub output: Vec<u8>,
+    }
+
+    /// What a proposal does on-chain when it is executed
+    ///
+    /// Every payload but `Signal` requires binary for/against voting options.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ProposalPayload {
+        /// Non-binding proposal, execution only records the outcome
+        Signal,
+        /// Transfer native funds from the treasury (Treasury proposals only)
+        Spend(TreasurySpend),
+        /// Dispatch cross-contract calls (Governance and Technical proposals only)
+        Calls(Vec<ProposalAction>),
+        /// Change the contract's own configuration (Governance proposals only)
+        Configure(ConfigChange),
+        /// Replace the contract code, keeping its storage (Technical proposals only)
+        Upgrade(H256),
+    }
+
+    /// Rules every proposal of one `ProposalType` must satisfy
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct TypePolicy {
+        /// Shortest voting period in blocks
+        pub min_voting_period: u32,
+        /// Lowest quorum in basis points
+        pub min_quorum_basis_points: u16,
+        /// Shortest execution delay in blocks
+        pub min_execution_delay: u32,
+        /// Roles allowed to propose, empty when any member or holder of `Role::Proposer` may
+        pub allowed_proposers: Vec<Role>,
+    }
+
+    /// A configuration change applied when a Governance proposal is executed
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum ConfigChange {
+        /// Replace the policy of a proposal type
+        SetTypePolicy(ProposalType, TypePolicy),
+        /// Replace the parameters used by `create_proposal_with_defaults`
+        SetDefaultParameters(GovernanceParameters),
+        /// Change who may vote and create proposals
+        SetMembershipPolicy(MembershipPolicy),
+        /// Hand the owner role to another account
+        SetOwner(AccountId),
+        /// Set or clear the guardian
+        SetGuardian(Option<AccountId>),
+        /// Replace the limits on proposal durations
+        SetDurationBounds(DurationBounds),
+        /// Replace the limits on proposal quorums
+        SetQuorumLimits(QuorumLimits),
+        /// Change how long passed proposals stay executable
+        SetExecutionGracePeriod(u32),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct Payout {
+        pub beneficiary: H160,
+        pub amount: Balance,
+        pub paid_at: u32,
     }
 
     #[derive(Debug, Clone, PartialEq, Eq)]
@@ -136,10 +510,47 @@ mod treasury_governance {
         pub proposer: AccountId,
         pub created_at: u32,
         pub voting_end: u32,
+        /// End of the reveal window of commit-reveal proposals, `voting_end` otherwise
+        pub reveal_end: u32,
         pub execution_time: u32,
         pub status: ProposalStatus,
         pub vote_counts: Vec<u128>,
         pub total_voters: u32,
+        /// Voting weight of all ballots cast, measured against quorum
+        pub turnout: u128,
+        /// Options that won once the proposal passed, most votes first
+        pub winning_options: Vec<u32>,
+        /// Why the proposal was rejected, cancelled or vetoed
+        pub rejection_reason: Option<RejectionReason>,
+        pub payload: ProposalPayload,
+        /// Registered voters when the proposal was created
+        pub electorate_size: u32,
+        /// Voting power available when the proposal was created, the quorum base
+        pub total_voting_power: u128,
+        /// Block whose governance token balances weight the votes, the one before creation
+        pub snapshot_block: u32,
+        /// Absolute quorum floor in force when the proposal was created
+        pub min_quorum_votes: u128,
+        /// Execution grace period in force when the proposal was created
+        pub execution_grace_period: u32,
+    }
+
+    impl Proposal {
+        /// Votes needed to reach quorum, measured against the creation-time snapshot
+        ///
+        /// The share of voting power is rounded up, and never falls below `min_quorum_votes`.
+        pub fn required_quorum_votes(&self) -> u128 {
+            let basis_points = self.governance_params.quorum_threshold.to_basis_points();
+            self.total_voting_power
+                .saturating_mul(basis_points as u128)
+                .div_ceil(10_000)
+                .max(self.min_quorum_votes)
+        }
+
+        /// Last block at which the proposal can still be executed once passed
+        pub fn execution_deadline(&self) -> u32 {
+            self.execution_time.saturating_add(self.execution_grace_period)
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Eq)]
@@ -147,9 +558,20 @@ mod treasury_governance {
     #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
     pub struct Vote {
         pub voter: AccountId,
-        pub choice: VoteChoice,
+        pub choices: Vec<VoteChoice>,
         pub timestamp: u32,
         pub weight: u128,
+        /// Voice credits spent on a quadratic proposal
+        pub credits_spent: u128,
+        /// Accounts whose delegated weight is included in `weight`
+        pub delegators: Vec<AccountId>,
+    }
+
+    /// Outcome of counting a proposal whose voting has closed
+    struct Tally {
+        rejection: Option<RejectionReason>,
+        winning_options: Vec<u32>,
+        runoff_rounds: Option<Vec<RunoffRound>>,
     }
 
     // ========== ERROR HANDLING ==========
@@ -164,10 +586,61 @@ mod treasury_governance {
         NotAuthorized,
         ProposalNotReadyForExecution,
         InvalidProposal,
+        ZeroAmount,
+        InsufficientTreasuryBalance,
+        TransferFailed,
+        NotRegistered,
+        NoVotingPower,
+        TokenQueryFailed,
+        DelegationCycle,
+        DelegationTooDeep,
+        NotDelegated,
+        VoteDelegated,
+        InvalidVotingMethod,
+        InsufficientCredits,
+        NotVoted,
+        NotInRevealPhase,
+        InvalidReveal,
+        InvalidSignature,
+        InvalidNonce,
+        ProposalHasVotes,
+        ProposalNotCancellable,
+        ProposalExpired,
+        ProposalNotExpired,
+        InvalidDuration,
+        InvalidQuorum,
+        UpgradeFailed,
+        TooManyDelegators,
+        InvalidRole,
+        PayloadNotAllowed,
+        NotPendingVoter,
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
 
+    // ========== CALL ENCODING ==========
+
+    /// Pre-encoded call arguments, written as-is into the call input
+    struct CallInput<'a>(&'a [u8]);
+
+    impl ink::scale::Encode for CallInput<'_> {
+        fn encode_to<T: ink::scale::Output + ?Sized>(&self, dest: &mut T) {
+            dest.write(self.0);
+        }
+    }
+
+    /// Raw return data of a call, captured without decoding
+    struct CallOutput(Vec<u8>);
+
+    impl ink::scale::Decode for CallOutput {
+        fn decode<I: ink::scale::Input>(input: &mut I) -> core::result::Result<Self, ink::scale::Error> {
+            let len = input.remaining_len()?.unwrap_or(0);
+            let mut bytes = vec![0u8; len];
+            input.read(&mut bytes)?;
+            Ok(Self(bytes))
+        }
+    }
+
     // ========== EVENTS ==========
 
     #[ink(event)]
@@ -190,11 +663,86 @@ mod treasury_governance {
         weight: u128,
     }
 
+    #[ink(event)]
+    pub struct VoteCommitted {
+        #[ink(topic)]
+        proposal_id: u32,
+        #[ink(topic)]
+        voter: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct VoteChanged {
+        #[ink(topic)]
+        proposal_id: u32,
+        #[ink(topic)]
+        voter: AccountId,
+        old_option: u32,
+        new_option: u32,
+        weight: u128,
+    }
+
+    #[ink(event)]
+    pub struct VoteRevoked {
+        #[ink(topic)]
+        proposal_id: u32,
+        #[ink(topic)]
+        voter: AccountId,
+        weight: u128,
+    }
+
+    #[ink(event)]
+    pub struct ProposalFinalized {
+        #[ink(topic)]
+        proposal_id: u32,
+        passed: bool,
+        reason: Option<RejectionReason>,
+    }
+
+    #[ink(event)]
+    pub struct ProposalStatusChanged {
+        #[ink(topic)]
+        proposal_id: u32,
+        old: ProposalStatus,
+        new: ProposalStatus,
+        reason: Option<RejectionReason>,
+        block: u32,
+    }
+
     #[ink(event)]
     pub struct ProposalExecuted {
         #[ink(topic)]
         proposal_id: u32,
         status: ProposalStatus,
+        payout: Option<TreasurySpend>,
+    }
+
+    #[ink(event)]
+    pub struct ProposalCancelled {
+        #[ink(topic)]
+        proposal_id: u32,
+        #[ink(topic)]
+        cancelled_by: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ProposalExpired {
+        #[ink(topic)]
+        proposal_id: u32,
+        execution_deadline: u32,
+    }
+
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        #[ink(topic)]
+        proposal_id: u32,
+        code_hash: H256,
+    }
+
+    #[ink(event)]
+    pub struct Migrated {
+        from_version: u32,
+        to_version: u32,
     }
 
     #[ink(event)]
@@ -203,713 +751,4826 @@ mod treasury_governance {
         voter: AccountId,
     }
 
-    // ========== STORAGE ==========
+    #[ink(event)]
+    pub struct VoterApplied {
+        #[ink(topic)]
+        applicant: AccountId,
+    }
 
-    #[ink(storage)]
-    pub struct TreasuryGovernance {
-        next_proposal_id: u32,
-        proposals: Mapping<u32, Proposal>,
-        votes: Mapping<(u32, AccountId), Vote>,
-        proposal_ids: Vec<u32>,
-        total_voters: u32,
-        owner: AccountId,
-        registered_voters: Mapping<AccountId, bool>,
+    #[ink(event)]
+    pub struct MembershipPolicyChanged {
+        policy: MembershipPolicy,
     }
 
-    // ========== IMPLEMENTATION ==========
+    #[ink(event)]
+    pub struct GuardianChanged {
+        guardian: Option<AccountId>,
+    }
 
-    impl TreasuryGovernance {
-        /// Constructor
-        #[ink(constructor)]
-        pub fn new() -> Self {
-            let caller_h160 = Self::env().caller();
-            // Convert H160 (20 bytes) to AccountId (32 bytes) by padding with zeros
-            let mut bytes = [0u8; 32];
-            bytes[12..32].copy_from_slice(caller_h160.as_ref());
-            let caller = AccountId::from(bytes);
+    #[ink(event)]
+    pub struct ExecutionGracePeriodChanged {
+        blocks: u32,
+    }
 
-            Self {
-                next_proposal_id: 1,
-                proposals: Mapping::default(),
-                votes: Mapping::default(),
-                proposal_ids: Vec::new(),
-                total_voters: 0,
-                owner: caller,
-                registered_voters: Mapping::default(),
-            }
-        }
+    #[ink(event)]
+    pub struct DurationBoundsChanged {
+        bounds: DurationBounds,
+    }
 
-        /// Register as a voter
-        #[ink(message)]
-        pub fn register_voter(&mut self) {
-            let caller_h160 = self.env().caller();
-            let mut bytes = [0u8; 32];
-            bytes[12..32].copy_from_slice(caller_h160.as_ref());
-            let caller = AccountId::from(bytes);
+    #[ink(event)]
+    pub struct QuorumLimitsChanged {
+        limits: QuorumLimits,
+    }
 
-            if self.registered_voters.get(caller).is_none() {
-                self.registered_voters.insert(caller, &true);
-                self.total_voters = self.total_voters.saturating_add(1);
+    #[ink(event)]
+    pub struct TypePolicyChanged {
+        proposal_type: ProposalType,
+        policy: TypePolicy,
+    }
 
-                self.env().emit_event(VoterRegistered { voter: caller });
-            }
-        }
+    #[ink(event)]
+    pub struct DefaultParametersChanged {
+        params: GovernanceParameters,
+    }
 
-        /// Create a new proposal
-        #[ink(message)]
-        pub fn create_proposal(
-            &mut self,
-            title: String,
-            description: String,
-            proposal_type: ProposalType,
-            governance_params: GovernanceParameters,
-            voting_options: VotingOptions,
-        ) -> Result<u32> {
-            // Validate voting options (1-10 options)
-            if voting_options.options.is_empty() || voting_options.options.len() > 10 {
-                return Err(Error::InvalidProposal);
-            }
+    #[ink(event)]
+    pub struct OwnerChanged {
+        #[ink(topic)]
+        old_owner: AccountId,
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
 
-            let proposer_h160 = self.env().caller();
-            let mut bytes = [0u8; 32];
-            bytes[12..32].copy_from_slice(proposer_h160.as_ref());
-            let proposer = AccountId::from(bytes);
+    #[ink(event)]
+    pub struct RoleGranted {
+        role: Role,
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        granted_by: AccountId,
+    }
 
-            let current_block = self.env().block_number();
-            let proposal_id = self.next_proposal_id;
+    #[ink(event)]
+    pub struct RoleRevoked {
+        role: Role,
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        revoked_by: AccountId,
+    }
 
-            // Calculate voting end time
-            let voting_blocks = governance_params.voting_period.to_blocks();
-            let voting_end = current_block.saturating_add(voting_blocks);
+    #[ink(event)]
+    pub struct DelegateSet {
+        #[ink(topic)]
+        delegator: AccountId,
+        #[ink(topic)]
+        delegate: AccountId,
+    }
 
-            // Calculate execution time
-            let execution_delay = governance_params.execution_delay.to_blocks();
-            let execution_time = voting_end.saturating_add(execution_delay);
+    #[ink(event)]
+    pub struct DelegateRemoved {
+        #[ink(topic)]
+        delegator: AccountId,
+        #[ink(topic)]
+        delegate: AccountId,
+    }
 
-            // Initialize vote counts
-            let vote_counts = vec![0u128; voting_options.options.len()];
+    #[ink(event)]
+    pub struct TreasuryDeposited {
+        #[ink(topic)]
+        depositor: AccountId,
+        amount: Balance,
+        total_contributed: Balance,
+    }
 
-            let proposal = Proposal {
-                id: proposal_id,
-                title: title.clone(),
-                description,
-                proposal_type,
-                governance_params,
-                voting_options,
-                proposer,
-                created_at: current_block,
-                voting_end,
-                execution_time,
-                status: ProposalStatus::Active,
-                vote_counts,
-                total_voters: 0,
-            };
+    // ========== STORAGE ==========
 
-            self.proposals.insert(proposal_id, &proposal);
-            self.proposal_ids.push(proposal_id);
-            self.next_proposal_id = self.next_proposal_id.saturating_add(1);
+    /// Contract storage
+    ///
+    /// Plain fields are decoded together from the root cell before any message runs,
+    /// so code replacing this contract must read them with exactly these types, in this
+    /// order. Data whose type may change between versions lives behind `Lazy` or
+    /// `Mapping`, which are only decoded on access; `migrate` rewrites those cells by
+    /// key after an upgrade. New fields are only ever `Lazy` or `Mapping`.
+    #[ink(storage)]
+    pub struct TreasuryGovernance {
+        next_proposal_id: u32,
+        proposals: Mapping<u32, Proposal>,
+        votes: Mapping<(u32, AccountId), Vote>,
+        proposal_ids: Vec<u32>,
+        total_voters: u32,
+        owner: AccountId,
+        registered_voters: Mapping<AccountId, bool>,
+        membership_policy: MembershipPolicy,
+        pending_voters: Mapping<AccountId, bool>,
+        contributions: Mapping<AccountId, Balance>,
+        total_deposited: Balance,
+        payouts: Mapping<u32, Payout>,
+        total_paid_out: Balance,
+        action_results: Mapping<u32, Vec<ActionResult>>,
+        governance_token: Option<H160>,
+        delegations: Mapping<AccountId, AccountId>,
+        delegators: Mapping<AccountId, Vec<AccountId>>,
+        /// Accounts delegating to an account directly or through others, at most `MAX_DELEGATION_SUBTREE`
+        delegator_counts: Mapping<AccountId, u32>,
+        /// Delegators whose weight was cast on a proposal, and the voter who cast it
+        delegated_votes: Mapping<(u32, AccountId), AccountId>,
+        /// Ranked ballots by proposal and position, `0..ranked_ballot_counts[proposal]`
+        ranked_ballots: Mapping<(u32, u32), RankedBallot>,
+        ranked_ballot_counts: Mapping<u32, u32>,
+        /// Position of each voter's ranked ballot on a proposal
+        ranked_ballot_positions: Mapping<(u32, AccountId), u32>,
+        runoff_rounds: Mapping<u32, Vec<RunoffRound>>,
+        commitments: Mapping<(u32, AccountId), [u8; 32]>,
+        /// Number of commitments ever made on a proposal
+        commitment_counts: Mapping<u32, u32>,
+        vote_nonces: Mapping<AccountId, u64>,
+        /// Account allowed to cancel proposals alongside the owner
+        guardian: Option<AccountId>,
+        /// Blocks after `execution_time` during which a passed proposal can be executed
+        execution_grace_period: u32,
+        duration_bounds: Lazy<DurationBounds>,
+        quorum_limits: Lazy<QuorumLimits>,
+        /// Policies by proposal type, changed only by executed Governance proposals
+        type_policies: Mapping<ProposalType, TypePolicy>,
+        /// Parameters used by `create_proposal_with_defaults`
+        default_params: Lazy<GovernanceParameters>,
+        /// Layout version of the data in storage, brought up to `STORAGE_VERSION` by `migrate`
+        storage_version: u32,
+        /// Roles granted explicitly, on top of those implied by ownership, the guardian and registration
+        roles: Mapping<(Role, AccountId), ()>,
+        /// Number of accounts each role is explicitly granted to
+        role_counts: Mapping<Role, u32>,
+    }
 
-            self.env().emit_event(ProposalCreated {
-                proposal_id,
-                proposer,
-                title,
-            });
+    // ========== IMPLEMENTATION ==========
 
-            Ok(proposal_id)
+    impl TreasuryGovernance {
+        /// Constructor with open membership
+        ///
+        /// Without registration or a token anyone can vote, so only `Signal` proposals are accepted.
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::with_membership_policy(MembershipPolicy::Open)
         }
 
-        /// Vote on a proposal
-        #[ink(message)]
-        pub fn vote(&mut self, proposal_id: u32, option_index: u32) -> Result<()> {
-            let voter_h160 = self.env().caller();
+        /// Constructor with an explicit membership policy
+        #[ink(constructor)]
+        pub fn with_membership_policy(membership_policy: MembershipPolicy) -> Self {
+            Self::init(membership_policy, None)
+        }
+
+        /// Constructor weighting votes by balances of a checkpointed PSP22 governance token
+        ///
+        /// The token must answer `PSP22Checkpoints::balance_of_at(owner, block)` and
+        /// `PSP22Checkpoints::total_supply_at(block)` with historical values. Votes are
+        /// weighted by balances at each proposal's `snapshot_block`, never live balances,
+        /// so tokens moved to another account after a proposal is created cannot vote twice.
+        ///
+        /// A plain PSP22 token has no history to answer with, so the token is probed with
+        /// `total_supply_at` and construction fails with `TokenQueryFailed` if it does not answer.
+        #[ink(constructor)]
+        pub fn with_governance_token(membership_policy: MembershipPolicy, governance_token: H160) -> Result<Self> {
+            let contract = Self::init(membership_policy, Some(governance_token));
+            contract.token_total_supply_at(governance_token, Self::env().block_number())?;
+            Ok(contract)
+        }
+
+        /// Shared constructor logic
+        fn init(membership_policy: MembershipPolicy, governance_token: Option<H160>) -> Self {
+            let caller_h160 = Self::env().caller();
+            // Convert H160 (20 bytes) to AccountId (32 bytes) by padding with zeros
             let mut bytes = [0u8; 32];
-            bytes[12..32].copy_from_slice(voter_h160.as_ref());
-            let voter = AccountId::from(bytes);
+            bytes[12..32].copy_from_slice(caller_h160.as_ref());
+            let caller = AccountId::from(bytes);
 
-            let current_block = self.env().block_number();
+            let mut contract = Self {
+                next_proposal_id: 1,
+                proposals: Mapping::default(),
+                votes: Mapping::default(),
+                proposal_ids: Vec::new(),
+                total_voters: 0,
+                owner: caller,
+                registered_voters: Mapping::default(),
+                membership_policy,
+                pending_voters: Mapping::default(),
+                contributions: Mapping::default(),
+                total_deposited: 0,
+                payouts: Mapping::default(),
+                total_paid_out: 0,
+                action_results: Mapping::default(),
+                governance_token,
+                delegations: Mapping::default(),
+                delegators: Mapping::default(),
+                delegator_counts: Mapping::default(),
+                delegated_votes: Mapping::default(),
+                ranked_ballots: Mapping::default(),
+                ranked_ballot_counts: Mapping::default(),
+                ranked_ballot_positions: Mapping::default(),
+                runoff_rounds: Mapping::default(),
+                commitments: Mapping::default(),
+                commitment_counts: Mapping::default(),
+                vote_nonces: Mapping::default(),
+                guardian: None,
+                execution_grace_period: DEFAULT_EXECUTION_GRACE_PERIOD,
+                duration_bounds: Lazy::new(),
+                quorum_limits: Lazy::new(),
+                type_policies: Mapping::default(),
+                default_params: Lazy::new(),
+                storage_version: STORAGE_VERSION,
+                roles: Mapping::default(),
+                role_counts: Mapping::default(),
+            };
+            // Written out so later code with other defaults keeps this contract's settings
+            contract.duration_bounds.set(&DurationBounds::default());
+            contract.quorum_limits.set(&QuorumLimits::default());
+            contract.default_params.set(&GovernanceParameters::default());
+            contract
+        }
 
-            // Check if proposal exists
-            let mut proposal = self.proposals.get(proposal_id)
-                .ok_or(Error::ProposalNotFound)?;
+        /// Register as a voter, or apply for registration under `OwnerApproved` membership
+        #[ink(message)]
+        pub fn register_voter(&mut self) {
+            let caller_h160 = self.env().caller();
+            let mut bytes = [0u8; 32];
+            bytes[12..32].copy_from_slice(caller_h160.as_ref());
+            let caller = AccountId::from(bytes);
 
-            // Check if proposal is active
-            if proposal.status != ProposalStatus::Active {
-                return Err(Error::ProposalNotActive);
+            if self.registered_voters.get(caller).is_some() {
+                return;
             }
 
-            // Check if voting period has ended
-            if current_block > proposal.voting_end {
-                return Err(Error::VotingPeriodEnded);
+            if self.membership_policy == MembershipPolicy::OwnerApproved {
+                if self.pending_voters.get(caller).is_none() {
+                    self.pending_voters.insert(caller, &true);
+                    self.env().emit_event(VoterApplied { applicant: caller });
+                }
+            } else {
+                self.add_voter(caller);
             }
+        }
 
-            // Check if already voted
-            if self.votes.get((proposal_id, voter)).is_some() {
-                return Err(Error::AlreadyVoted);
-            }
+        /// Approve a voter registration (admins only)
+        ///
+        /// Only accounts that applied with `register_voter` can be approved; others fail
+        /// with `NotPendingVoter`.
+        #[ink(message)]
+        pub fn approve_voter(&mut self, voter: AccountId) -> Result<()> {
+            self.ensure_role(Role::Admin)?;
 
-            // Validate option index
-            if option_index as usize >= proposal.voting_options.options.len() {
-                return Err(Error::InvalidProposal);
+            if self.pending_voters.get(voter).is_none() {
+                return Err(Error::NotPendingVoter);
             }
 
-            // Get option text
-            let option_text = proposal.voting_options.options[option_index as usize].clone();
-
-            // Create vote with weight of 1
-            let vote = Vote {
-                voter,
-                choice: VoteChoice {
-                    option_index,
-                    option_text: option_text.clone(),
-                },
-                timestamp: current_block,
-                weight: 1,
-            };
+            self.pending_voters.remove(voter);
+            if self.registered_voters.get(voter).is_none() {
+                self.add_voter(voter);
+            }
 
-            // Update vote counts
-            proposal.vote_counts[option_index as usize] = 
-                proposal.vote_counts[option_index as usize].saturating_add(1);
-            
-            proposal.total_voters = proposal.total_voters.saturating_add(1);
+            Ok(())
+        }
 
-            // Store vote and update proposal
-            self.votes.insert((proposal_id, voter), &vote);
-            self.proposals.insert(proposal_id, &proposal);
+        /// Change who may vote and create proposals (admins only)
+        #[ink(message)]
+        pub fn set_membership_policy(&mut self, policy: MembershipPolicy) -> Result<()> {
+            self.ensure_role(Role::Admin)?;
 
-            self.env().emit_event(VoteCast {
-                proposal_id,
-                voter,
-                option_index,
-                option_text,
-                weight: 1,
-            });
+            self.membership_policy = policy.clone();
+            self.env().emit_event(MembershipPolicyChanged { policy });
 
             Ok(())
         }
 
-        /// Update proposal status after voting period
+        /// Grant a role to an account (admins only)
+        ///
+        /// `Member` follows registration and fails with `InvalidRole`.
         #[ink(message)]
-        pub fn update_proposal_status(&mut self, proposal_id: u32) -> Result<()> {
-            let current_block = self.env().block_number();
-
-            let mut proposal = self.proposals.get(proposal_id)
-                .ok_or(Error::ProposalNotFound)?;
+        pub fn grant_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            self.ensure_role(Role::Admin)?;
 
-            // Only update if active and voting period ended
-            if proposal.status != ProposalStatus::Active {
-                return Ok(());
+            if role == Role::Member {
+                return Err(Error::InvalidRole);
             }
 
-            if current_block <= proposal.voting_end {
-                return Ok(());
+            if !self.roles.contains((role.clone(), account)) {
+                self.roles.insert((role.clone(), account), &());
+                let count = self.role_counts.get(&role).unwrap_or(0);
+                self.role_counts.insert(&role, &count.saturating_add(1));
+                self.env().emit_event(RoleGranted {
+                    role,
+                    account,
+                    granted_by: self.caller_account(),
+                });
             }
 
-            // Calculate quorum
-            let quorum_percentage = proposal.governance_params.quorum_threshold.to_percentage();
-            let required_votes = (self.total_voters as u128)
-                .saturating_mul(quorum_percentage as u128) / 100;
-
-            let total_votes: u128 = proposal.vote_counts.iter().sum();
-
-            // Check if quorum reached
-            if total_votes < required_votes {
-                proposal.status = ProposalStatus::Rejected;
-                self.proposals.insert(proposal_id, &proposal);
-                return Ok(());
-            }
+            Ok(())
+        }
 
-            // Find winning option (highest vote count)
-            let mut max_votes = 0u128;
-            let mut winning_count: u32 = 0;
+        /// Revoke an explicitly granted role (admins only)
+        ///
+        /// Roles implied by ownership, the guardian slot or registration are not affected.
+        /// `Member` follows registration and fails with `InvalidRole`.
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+            self.ensure_role(Role::Admin)?;
 
-            for &votes in &proposal.vote_counts {
-                if votes > max_votes {
-                    max_votes = votes;
-                    winning_count = 1;
-                } else if votes == max_votes && votes > 0 {
-                    winning_count = winning_count.saturating_add(1);
-                }
+            if role == Role::Member {
+                return Err(Error::InvalidRole);
             }
 
-            // Handle ties (mark as rejected)
-            if winning_count > 1 {
-                proposal.status = ProposalStatus::Rejected;
-            } else {
-                proposal.status = ProposalStatus::Passed;
+            if self.roles.contains((role.clone(), account)) {
+                self.roles.remove((role.clone(), account));
+                let count = self.role_counts.get(&role).unwrap_or(0);
+                self.role_counts.insert(&role, &count.saturating_sub(1));
+                self.env().emit_event(RoleRevoked {
+                    role,
+                    account,
+                    revoked_by: self.caller_account(),
+                });
             }
 
-            self.proposals.insert(proposal_id, &proposal);
             Ok(())
         }
 
-        /// Execute a passed proposal
+        /// Set or clear the guardian allowed to cancel proposals (admins only)
         #[ink(message)]
-        pub fn execute_proposal(&mut self, proposal_id: u32) -> Result<()> {
-            let current_block = self.env().block_number();
-
-            let mut proposal = self.proposals.get(proposal_id)
-                .ok_or(Error::ProposalNotFound)?;
-
-            // Check if proposal is in passed status
-            if proposal.status != ProposalStatus::Passed {
-                return Err(Error::ProposalNotReadyForExecution);
-            }
-
-            // Check if execution delay has passed
-            if current_block < proposal.execution_time {
-                return Err(Error::ProposalNotReadyForExecution);
-            }
-
-            proposal.status = ProposalStatus::Executed;
-            self.proposals.insert(proposal_id, &proposal);
+        pub fn set_guardian(&mut self, guardian: Option<AccountId>) -> Result<()> {
+            self.ensure_role(Role::Admin)?;
 
-            self.env().emit_event(ProposalExecuted {
-                proposal_id,
-                status: ProposalStatus::Executed,
-            });
+            self.guardian = guardian;
+            self.env().emit_event(GuardianChanged { guardian });
 
             Ok(())
         }
 
-        // ========== QUERY FUNCTIONS ==========
-
-        /// Get a specific proposal
+        /// Set how many blocks passed proposals stay executable after their execution time (admins only)
+        ///
+        /// Applies to proposals created afterwards; existing proposals keep the period they were created with.
         #[ink(message)]
-        pub fn get_proposal(&self, proposal_id: u32) -> Option<Proposal> {
-            self.proposals.get(proposal_id)
-        }
+        pub fn set_execution_grace_period(&mut self, blocks: u32) -> Result<()> {
+            self.ensure_role(Role::Admin)?;
 
-        /// Get all proposal IDs
-        #[ink(message)]
-        pub fn get_all_proposal_ids(&self) -> Vec<u32> {
-            self.proposal_ids.clone()
-        }
+            self.execution_grace_period = blocks;
+            self.env().emit_event(ExecutionGracePeriodChanged { blocks });
 
-        /// Get user's vote on a proposal
-        #[ink(message)]
-        pub fn get_user_vote(&self, proposal_id: u32, user: AccountId) -> Option<Vote> {
-            self.votes.get((proposal_id, user))
+            Ok(())
         }
 
-        /// Get total registered voters
+        /// Set the limits proposal durations are validated against (admins only)
         #[ink(message)]
-        pub fn get_total_voters(&self) -> u32 {
-            self.total_voters
-        }
+        pub fn set_duration_bounds(&mut self, bounds: DurationBounds) -> Result<()> {
+            self.ensure_role(Role::Admin)?;
 
-        /// Check if proposal reached quorum
-        #[ink(message)]
-        pub fn has_reached_quorum(&self, proposal_id: u32) -> bool {
-            if let Some(proposal) = self.proposals.get(proposal_id) {
-                let quorum_percentage = proposal.governance_params.quorum_threshold.to_percentage();
-                let required_votes = (self.total_voters as u128)
-                    .saturating_mul(quorum_percentage as u128) / 100;
-                
-                let total_votes: u128 = proposal.vote_counts.iter().sum();
-                total_votes >= required_votes
-            } else {
-                false
+            if !bounds.is_valid() {
+                return Err(Error::InvalidDuration);
             }
+
+            self.duration_bounds.set(&bounds);
+            self.env().emit_event(DurationBoundsChanged { bounds });
+
+            Ok(())
         }
 
-        /// Get proposal results
+        /// Set the quorum range proposals may choose and the absolute vote floor (admins only)
         #[ink(message)]
-        pub fn get_proposal_results(&self, proposal_id: u32) -> Option<(Vec<u128>, bool)> {
-            if let Some(proposal) = self.proposals.get(proposal_id) {
-                let reached_quorum = self.has_reached_quorum(proposal_id);
-                Some((proposal.vote_counts, reached_quorum))
-            } else {
-                None
+        pub fn set_quorum_limits(&mut self, limits: QuorumLimits) -> Result<()> {
+            self.ensure_role(Role::Admin)?;
+
+            if !limits.is_valid() {
+                return Err(Error::InvalidQuorum);
             }
+
+            self.quorum_limits.set(&limits);
+            self.env().emit_event(QuorumLimitsChanged { limits });
+
+            Ok(())
         }
 
-        /// Get voting options for a proposal
+        /// Delegate the caller's voting weight to another registered voter
         #[ink(message)]
-        pub fn get_voting_options(&self, proposal_id: u32) -> Option<Vec<String>> {
-            if let Some(proposal) = self.proposals.get(proposal_id) {
-                Some(proposal.voting_options.options)
-            } else {
-                None
+        pub fn delegate(&mut self, to: AccountId) -> Result<()> {
+            let delegator = self.caller_account();
+
+            if !self.is_registered(delegator) || !self.is_registered(to) {
+                return Err(Error::NotRegistered);
             }
-        }
 
-        /// Get detailed results with option names
-        #[ink(message)]
-        pub fn get_detailed_results(&self, proposal_id: u32) -> Option<Vec<(String, u128)>> {
-            if let Some(proposal) = self.proposals.get(proposal_id) {
-                let mut results = Vec::new();
-                for (i, option) in proposal.voting_options.options.iter().enumerate() {
-                    results.push((option.clone(), proposal.vote_counts[i]));
+            // Walk the chain above `to`, rejecting cycles and over-long chains
+            let mut current = to;
+            let mut depth = 1u32;
+            loop {
+                if current == delegator {
+                    return Err(Error::DelegationCycle);
+                }
+                match self.delegations.get(current) {
+                    Some(next) => {
+                        depth = depth.saturating_add(1);
+                        if depth > MAX_DELEGATION_DEPTH {
+                            return Err(Error::DelegationTooDeep);
+                        }
+                        current = next;
+                    }
+                    None => break,
+                }
+            }
+
+            // Accounts delegating to the caller move down with it, so their chains grow too
+            if depth.saturating_add(self.delegation_height(delegator)) > MAX_DELEGATION_DEPTH {
+                return Err(Error::DelegationTooDeep);
+            }
+
+            // Every account above the caller takes on its whole subtree, which must stay bounded
+            let moved = self.delegator_counts.get(delegator).unwrap_or(0).saturating_add(1);
+            let previous = self.delegations.get(delegator);
+            let leaving = previous.map(|previous| self.delegation_chain(previous)).unwrap_or_default();
+            for account in self.delegation_chain(to) {
+                let mut count = self.delegator_counts.get(account).unwrap_or(0);
+                if leaving.contains(&account) {
+                    count = count.saturating_sub(moved);
+                }
+                if count.saturating_add(moved) > MAX_DELEGATION_SUBTREE {
+                    return Err(Error::TooManyDelegators);
+                }
+            }
+
+            if let Some(previous) = previous {
+                self.remove_delegator(previous, delegator);
+                self.update_delegator_counts(previous, moved, false);
+                self.env().emit_event(DelegateRemoved { delegator, delegate: previous });
+            }
+
+            self.delegations.insert(delegator, &to);
+            let mut delegators = self.delegators.get(to).unwrap_or_default();
+            delegators.push(delegator);
+            self.delegators.insert(to, &delegators);
+            self.update_delegator_counts(to, moved, true);
+
+            self.env().emit_event(DelegateSet { delegator, delegate: to });
+
+            Ok(())
+        }
+
+        /// Take back the caller's delegated voting weight
+        #[ink(message)]
+        pub fn undelegate(&mut self) -> Result<()> {
+            let delegator = self.caller_account();
+
+            let delegate = self.delegations.get(delegator)
+                .ok_or(Error::NotDelegated)?;
+
+            self.delegations.remove(delegator);
+            self.remove_delegator(delegate, delegator);
+            let moved = self.delegator_counts.get(delegator).unwrap_or(0).saturating_add(1);
+            self.update_delegator_counts(delegate, moved, false);
+
+            self.env().emit_event(DelegateRemoved { delegator, delegate });
+
+            Ok(())
+        }
+
+        /// Deposit native funds into the treasury
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> Result<()> {
+            let depositor = self.caller_account();
+            let amount: Balance = self.env().transferred_value().as_u128();
+
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+
+            let total_contributed = self.contributions.get(depositor)
+                .unwrap_or(0)
+                .saturating_add(amount);
+            self.contributions.insert(depositor, &total_contributed);
+            self.total_deposited = self.total_deposited.saturating_add(amount);
+
+            self.env().emit_event(TreasuryDeposited {
+                depositor,
+                amount,
+                total_contributed,
+            });
+
+            Ok(())
+        }
+
+        /// Create a new proposal
+        #[ink(message)]
+        pub fn create_proposal(
+            &mut self,
+            title: String,
+            description: String,
+            proposal_type: ProposalType,
+            governance_params: GovernanceParameters,
+            voting_options: VotingOptions,
+        ) -> Result<u32> {
+            self.insert_proposal(
+                title,
+                description,
+                proposal_type,
+                governance_params,
+                voting_options,
+                ProposalPayload::Signal,
+            )
+        }
+
+        /// Create a signal proposal using the contract's default governance parameters
+        #[ink(message)]
+        pub fn create_proposal_with_defaults(
+            &mut self,
+            title: String,
+            description: String,
+            proposal_type: ProposalType,
+            voting_options: VotingOptions,
+        ) -> Result<u32> {
+            self.insert_proposal(
+                title,
+                description,
+                proposal_type,
+                self.get_default_params(),
+                voting_options,
+                ProposalPayload::Signal,
+            )
+        }
+
+        /// Create a new proposal that performs an on-chain action when executed
+        #[ink(message)]
+        pub fn create_proposal_with_payload(
+            &mut self,
+            title: String,
+            description: String,
+            proposal_type: ProposalType,
+            governance_params: GovernanceParameters,
+            voting_options: VotingOptions,
+            payload: ProposalPayload,
+        ) -> Result<u32> {
+            self.insert_proposal(
+                title,
+                description,
+                proposal_type,
+                governance_params,
+                voting_options,
+                payload,
+            )
+        }
+
+        /// Validate and store a new proposal
+        fn insert_proposal(
+            &mut self,
+            title: String,
+            description: String,
+            proposal_type: ProposalType,
+            governance_params: GovernanceParameters,
+            voting_options: VotingOptions,
+            payload: ProposalPayload,
+        ) -> Result<u32> {
+            // Validate voting options (1-10 options)
+            if voting_options.options.is_empty() || voting_options.options.len() > 10 {
+                return Err(Error::InvalidProposal);
+            }
+
+            // Roles, when given, must cover every option
+            if !voting_options.roles.is_empty() && voting_options.roles.len() != voting_options.options.len() {
+                return Err(Error::InvalidProposal);
+            }
+
+            // Ranked and approval tallies treat every option as a candidate, so their options
+            // cannot be designated for, against or abstain
+            if matches!(
+                governance_params.voting_method,
+                VotingMethod::RankedChoice | VotingMethod::Approval { .. }
+            ) && voting_options.roles.iter().any(|role| *role != OptionRole::Choice)
+            {
+                return Err(Error::InvalidProposal);
+            }
+
+            // Binary proposals have one for and one against option besides abstentions
+            if voting_options.is_binary() {
+                let count = |role: OptionRole| voting_options.roles.iter().filter(|r| **r == role).count();
+                if count(OptionRole::For) != 1
+                    || count(OptionRole::Against) != 1
+                    || count(OptionRole::Choice) != 0
+                {
+                    return Err(Error::InvalidProposal);
+                }
+            }
+
+            // Members may propose, as may holders of the Proposer role under any membership policy
+            let caller = self.caller_account();
+            if !self.has_role(Role::Proposer, caller) {
+                self.ensure_member(caller)?;
+            }
+
+            // Validate voting method settings
+            match governance_params.voting_method {
+                VotingMethod::Quadratic { credits_per_voter: 0 } => {
+                    return Err(Error::InvalidProposal);
+                }
+                VotingMethod::Approval { winners }
+                    if winners == 0 || winners as usize > voting_options.options.len() =>
+                {
+                    return Err(Error::InvalidProposal);
+                }
+                _ => {}
+            }
+
+            // Validate durations against the configured bounds
+            let bounds = self.get_duration_bounds();
+            let voting_period_allowed = |period: &VotingPeriod| {
+                (bounds.min_voting_period..=bounds.max_voting_period).contains(&period.to_blocks())
+            };
+            if !voting_period_allowed(&governance_params.voting_period) {
+                return Err(Error::InvalidDuration);
+            }
+            if let VotingMethod::CommitReveal { reveal_period } = &governance_params.voting_method {
+                if !voting_period_allowed(reveal_period) {
+                    return Err(Error::InvalidDuration);
+                }
+            }
+            if !(bounds.min_execution_delay..=bounds.max_execution_delay)
+                .contains(&governance_params.execution_delay.to_blocks())
+            {
+                return Err(Error::InvalidDuration);
+            }
+
+            // Validate the quorum against the configured limits
+            let quorum_basis_points = governance_params.quorum_threshold.to_basis_points();
+            let quorum_limits = self.get_quorum_limits();
+            if quorum_basis_points < quorum_limits.min_basis_points
+                || quorum_basis_points > quorum_limits.max_basis_points
+            {
+                return Err(Error::InvalidQuorum);
+            }
+
+            // Payloads other than signals act on-chain, so they need a for option to vote on;
+            // the binary tally only passes when that option carries the vote
+            if payload != ProposalPayload::Signal && !voting_options.is_binary() {
+                return Err(Error::InvalidProposal);
+            }
+            self.ensure_payload_allowed(&payload)?;
+
+            // Validate payload against the proposal type
+            match &payload {
+                ProposalPayload::Signal => {}
+                ProposalPayload::Spend(spend) => {
+                    if proposal_type != ProposalType::Treasury {
+                        return Err(Error::InvalidProposal);
+                    }
+                    if spend.amount == 0 {
+                        return Err(Error::ZeroAmount);
+                    }
+                }
+                ProposalPayload::Calls(actions) => {
+                    if !matches!(proposal_type, ProposalType::Governance | ProposalType::Technical) {
+                        return Err(Error::InvalidProposal);
+                    }
+                    if actions.is_empty() || actions.len() > MAX_PROPOSAL_ACTIONS {
+                        return Err(Error::InvalidProposal);
+                    }
+                }
+                ProposalPayload::Upgrade(_) => {
+                    if proposal_type != ProposalType::Technical {
+                        return Err(Error::InvalidProposal);
+                    }
+                }
+                ProposalPayload::Configure(change) => {
+                    if proposal_type != ProposalType::Governance {
+                        return Err(Error::InvalidProposal);
+                    }
+                    match change {
+                        ConfigChange::SetDurationBounds(bounds) if !bounds.is_valid() => {
+                            return Err(Error::InvalidDuration);
+                        }
+                        ConfigChange::SetQuorumLimits(limits) if !limits.is_valid() => {
+                            return Err(Error::InvalidQuorum);
+                        }
+                        ConfigChange::SetTypePolicy(proposal_type, policy) => {
+                            self.validate_type_policy(proposal_type, policy)?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            // Enforce the policy of the proposal type
+            let policy = self.get_type_policy(proposal_type.clone());
+            if !policy.allowed_proposers.is_empty()
+                && !policy.allowed_proposers.iter().any(|role| self.has_role(role.clone(), caller))
+            {
+                return Err(Error::NotAuthorized);
+            }
+            if governance_params.voting_period.to_blocks() < policy.min_voting_period
+                || governance_params.execution_delay.to_blocks() < policy.min_execution_delay
+            {
+                return Err(Error::InvalidDuration);
+            }
+            if quorum_basis_points < policy.min_quorum_basis_points {
+                return Err(Error::InvalidQuorum);
+            }
+
+            let current_block = self.env().block_number();
+            let proposal_id = self.next_proposal_id;
+
+            // Calculate voting end time
+            let voting_blocks = governance_params.voting_period.to_blocks();
+            let voting_end = current_block.saturating_add(voting_blocks);
+
+            // Commit-reveal proposals open a reveal window after voting
+            let reveal_end = match &governance_params.voting_method {
+                VotingMethod::CommitReveal { reveal_period } => voting_end.saturating_add(reveal_period.to_blocks()),
+                _ => voting_end,
+            };
+
+            // Calculate execution time
+            let execution_delay = governance_params.execution_delay.to_blocks();
+            let execution_time = reveal_end.saturating_add(execution_delay);
+
+            // Initialize vote counts
+            let vote_counts = vec![0u128; voting_options.options.len()];
+
+            // Snapshot the voting power quorum is measured against. Token balances are read
+            // at the previous block, which can no longer change once this proposal exists
+            let snapshot_block = current_block.saturating_sub(1);
+            let total_voting_power = match self.governance_token {
+                Some(token) => self.token_total_supply_at(token, snapshot_block)?,
+                None => self.total_voters as u128,
+            };
+
+            let proposal = Proposal {
+                id: proposal_id,
+                title: title.clone(),
+                description,
+                proposal_type,
+                governance_params,
+                voting_options,
+                proposer: caller,
+                created_at: current_block,
+                voting_end,
+                reveal_end,
+                execution_time,
+                status: ProposalStatus::Active,
+                vote_counts,
+                total_voters: 0,
+                turnout: 0,
+                winning_options: Vec::new(),
+                rejection_reason: None,
+                payload,
+                electorate_size: self.total_voters,
+                total_voting_power,
+                snapshot_block,
+                min_quorum_votes: quorum_limits.min_votes,
+                execution_grace_period: self.execution_grace_period,
+            };
+
+            self.proposals.insert(proposal_id, &proposal);
+            self.proposal_ids.push(proposal_id);
+            self.next_proposal_id = self.next_proposal_id.saturating_add(1);
+
+            self.env().emit_event(ProposalCreated {
+                proposal_id,
+                proposer: caller,
+                title,
+            });
+
+            Ok(proposal_id)
+        }
+
+        /// Vote on a proposal
+        #[ink(message)]
+        pub fn vote(&mut self, proposal_id: u32, option_index: u32) -> Result<()> {
+            let voter_h160 = self.env().caller();
+            let mut bytes = [0u8; 32];
+            bytes[12..32].copy_from_slice(voter_h160.as_ref());
+            let voter = AccountId::from(bytes);
+
+            self.cast_vote(voter, proposal_id, option_index)
+        }
+
+        /// Record a batch of single-choice votes signed off-chain by their voters
+        ///
+        /// Each ballot is signed with the voter's secp256k1 key over
+        /// `signed_vote_digest(proposal_id, option_index, nonce)`. The batch is
+        /// all-or-nothing: any invalid ballot fails the whole call.
+        #[ink(message)]
+        pub fn submit_signed_votes(&mut self, ballots: Vec<SignedBallot>) -> Result<()> {
+            for ballot in ballots {
+                let voter = Self::to_account_id(ballot.voter);
+
+                let nonce = self.vote_nonces.get(voter).unwrap_or(0);
+                if ballot.nonce != nonce {
+                    return Err(Error::InvalidNonce);
+                }
+
+                let digest = self.signed_vote_digest(ballot.proposal_id, ballot.option_index, ballot.nonce);
+                let public_key = self.env()
+                    .ecdsa_recover(&ballot.signature, &digest)
+                    .map_err(|_| Error::InvalidSignature)?;
+                let signer = self.env()
+                    .ecdsa_to_eth_address(&public_key)
+                    .map_err(|_| Error::InvalidSignature)?;
+                if signer != <[u8; 20]>::from(ballot.voter) {
+                    return Err(Error::InvalidSignature);
+                }
+
+                self.vote_nonces.insert(voter, &nonce.saturating_add(1));
+                self.cast_vote(voter, ballot.proposal_id, ballot.option_index)?;
+            }
+
+            Ok(())
+        }
+
+        /// Vote on a quadratic proposal by spending voice credits across options
+        ///
+        /// Each allocation is `(option_index, credits)`; the option receives the
+        /// integer square root of the credits spent on it.
+        #[ink(message)]
+        pub fn vote_quadratic(&mut self, proposal_id: u32, allocations: Vec<(u32, u128)>) -> Result<()> {
+            let voter = self.caller_account();
+
+            let (proposal, delegators, weight) = self.prepare_ballot(voter, proposal_id)?;
+
+            let VotingMethod::Quadratic { credits_per_voter } = proposal.governance_params.voting_method else {
+                return Err(Error::InvalidVotingMethod);
+            };
+
+            if allocations.is_empty() {
+                return Err(Error::InvalidProposal);
+            }
+
+            let mut credits_spent = 0u128;
+            let mut choices: Vec<VoteChoice> = Vec::with_capacity(allocations.len());
+            for (option_index, credits) in allocations {
+                if option_index as usize >= proposal.voting_options.options.len()
+                    || credits == 0
+                    || choices.iter().any(|choice| choice.option_index == option_index)
+                {
+                    return Err(Error::InvalidProposal);
+                }
+
+                credits_spent = credits_spent.saturating_add(credits);
+                choices.push(VoteChoice {
+                    option_index,
+                    option_text: proposal.voting_options.options[option_index as usize].clone(),
+                    weight: credits.isqrt(),
+                });
+            }
+
+            // Every unit of voting weight, own or delegated, buys the same credit budget
+            if credits_spent > credits_per_voter.saturating_mul(weight) {
+                return Err(Error::InsufficientCredits);
+            }
+
+            self.record_ballot(proposal, voter, choices, weight, delegators, credits_spent);
+            Ok(())
+        }
+
+        /// Vote on a ranked-choice proposal with options listed in order of preference
+        ///
+        /// Options left out of the ranking are never counted for this ballot.
+        #[ink(message)]
+        pub fn vote_ranked(&mut self, proposal_id: u32, ranking: Vec<u32>) -> Result<()> {
+            let voter = self.caller_account();
+
+            let (proposal, delegators, weight) = self.prepare_ballot(voter, proposal_id)?;
+
+            if proposal.governance_params.voting_method != VotingMethod::RankedChoice {
+                return Err(Error::InvalidVotingMethod);
+            }
+
+            if ranking.is_empty() {
+                return Err(Error::InvalidProposal);
+            }
+
+            // The first preference carries the weight in `vote_counts`; the rest record the ranking
+            let mut choices: Vec<VoteChoice> = Vec::with_capacity(ranking.len());
+            for &option_index in &ranking {
+                if option_index as usize >= proposal.voting_options.options.len()
+                    || choices.iter().any(|choice| choice.option_index == option_index)
+                {
+                    return Err(Error::InvalidProposal);
+                }
+
+                choices.push(VoteChoice {
+                    option_index,
+                    option_text: proposal.voting_options.options[option_index as usize].clone(),
+                    weight: if choices.is_empty() { weight } else { 0 },
+                });
+            }
+
+            let position = self.ranked_ballot_counts.get(proposal_id).unwrap_or(0);
+            self.ranked_ballots.insert((proposal_id, position), &RankedBallot { voter, ranking, weight });
+            self.ranked_ballot_positions.insert((proposal_id, voter), &position);
+            self.ranked_ballot_counts.insert(proposal_id, &position.saturating_add(1));
+
+            self.record_ballot(proposal, voter, choices, weight, delegators, 0);
+            Ok(())
+        }
+
+        /// Vote on an approval proposal, approving any subset of its options
+        #[ink(message)]
+        pub fn vote_approval(&mut self, proposal_id: u32, approved: Vec<u32>) -> Result<()> {
+            let voter = self.caller_account();
+
+            let (proposal, delegators, weight) = self.prepare_ballot(voter, proposal_id)?;
+
+            if !matches!(proposal.governance_params.voting_method, VotingMethod::Approval { .. }) {
+                return Err(Error::InvalidVotingMethod);
+            }
+
+            if approved.is_empty() {
+                return Err(Error::InvalidProposal);
+            }
+
+            // Every approved option receives the voter's full weight
+            let mut choices: Vec<VoteChoice> = Vec::with_capacity(approved.len());
+            for option_index in approved {
+                if option_index as usize >= proposal.voting_options.options.len()
+                    || choices.iter().any(|choice| choice.option_index == option_index)
+                {
+                    return Err(Error::InvalidProposal);
+                }
+
+                choices.push(VoteChoice {
+                    option_index,
+                    option_text: proposal.voting_options.options[option_index as usize].clone(),
+                    weight,
+                });
+            }
+
+            self.record_ballot(proposal, voter, choices, weight, delegators, 0);
+            Ok(())
+        }
+
+        /// Commit to a hidden vote on a commit-reveal proposal
+        ///
+        /// The commitment is the Keccak-256 hash of the SCALE-encoded tuple
+        /// `(proposal_id: u32, voter: AccountId, option_index: u32, salt: [u8; 32])`.
+        #[ink(message)]
+        pub fn commit_vote(&mut self, proposal_id: u32, commitment: [u8; 32]) -> Result<()> {
+            let voter = self.caller_account();
+            self.ensure_member(voter)?;
+
+            let proposal = self.open_proposal(proposal_id)?;
+            self.ensure_payload_allowed(&proposal.payload)?;
+
+            if !matches!(proposal.governance_params.voting_method, VotingMethod::CommitReveal { .. }) {
+                return Err(Error::InvalidVotingMethod);
+            }
+
+            if self.commitments.contains((proposal_id, voter))
+                || self.delegated_votes.contains((proposal_id, voter))
+            {
+                return Err(Error::AlreadyVoted);
+            }
+
+            if self.delegations.contains(voter) {
+                return Err(Error::VoteDelegated);
+            }
+
+            self.commitments.insert((proposal_id, voter), &commitment);
+            let commitment_count = self.commitment_counts.get(proposal_id).unwrap_or(0);
+            self.commitment_counts.insert(proposal_id, &commitment_count.saturating_add(1));
+            self.env().emit_event(VoteCommitted { proposal_id, voter });
+
+            Ok(())
+        }
+
+        /// Reveal a committed vote during the reveal window
+        ///
+        /// Weight, including delegated weight, is counted at reveal time. Commitments
+        /// that are never revealed are not counted.
+        #[ink(message)]
+        pub fn reveal_vote(&mut self, proposal_id: u32, option_index: u32, salt: [u8; 32]) -> Result<()> {
+            let voter = self.caller_account();
+            let current_block = self.env().block_number();
+
+            let proposal = self.proposals.get(proposal_id)
+                .ok_or(Error::ProposalNotFound)?;
+            self.ensure_payload_allowed(&proposal.payload)?;
+
+            if !matches!(proposal.governance_params.voting_method, VotingMethod::CommitReveal { .. }) {
+                return Err(Error::InvalidVotingMethod);
+            }
+
+            if !matches!(proposal.status, ProposalStatus::Active | ProposalStatus::Revealing)
+                || current_block <= proposal.voting_end
+                || current_block > proposal.reveal_end
+            {
+                return Err(Error::NotInRevealPhase);
+            }
+
+            let commitment = self.commitments.get((proposal_id, voter))
+                .ok_or(Error::NotVoted)?;
+
+            if self.delegated_votes.contains((proposal_id, voter)) {
+                return Err(Error::AlreadyVoted);
+            }
+
+            let expected = self.env()
+                .hash_encoded::<Keccak256, _>(&(proposal_id, voter, option_index, salt));
+            if expected != commitment {
+                return Err(Error::InvalidReveal);
+            }
+
+            if option_index as usize >= proposal.voting_options.options.len() {
+                return Err(Error::InvalidProposal);
+            }
+
+            let (delegators, delegated_weight) = self.collect_delegators(&proposal, voter)?;
+            let weight = self.base_weight(voter, proposal.snapshot_block)?.saturating_add(delegated_weight);
+            if weight == 0 {
+                return Err(Error::NoVotingPower);
+            }
+
+            let choices = vec![VoteChoice {
+                option_index,
+                option_text: proposal.voting_options.options[option_index as usize].clone(),
+                weight,
+            }];
+
+            self.commitments.remove((proposal_id, voter));
+            self.record_ballot(proposal, voter, choices, weight, delegators, 0);
+            Ok(())
+        }
+
+        /// Move the caller's single-choice vote to another option while voting is open
+        #[ink(message)]
+        pub fn change_vote(&mut self, proposal_id: u32, new_option: u32) -> Result<()> {
+            let voter = self.caller_account();
+
+            let mut proposal = self.open_proposal(proposal_id)?;
+            let mut vote = self.votes.get((proposal_id, voter))
+                .ok_or(Error::NotVoted)?;
+
+            if proposal.governance_params.voting_method != VotingMethod::SingleChoice {
+                return Err(Error::InvalidVotingMethod);
+            }
+
+            if new_option as usize >= proposal.voting_options.options.len() {
+                return Err(Error::InvalidProposal);
+            }
+
+            let old_option = vote.choices.first()
+                .map(|choice| choice.option_index)
+                .ok_or(Error::NotVoted)?;
+
+            // Move the full weight, delegated weight included, to the new option
+            proposal.vote_counts[old_option as usize] =
+                proposal.vote_counts[old_option as usize].saturating_sub(vote.weight);
+            proposal.vote_counts[new_option as usize] =
+                proposal.vote_counts[new_option as usize].saturating_add(vote.weight);
+
+            vote.choices = vec![VoteChoice {
+                option_index: new_option,
+                option_text: proposal.voting_options.options[new_option as usize].clone(),
+                weight: vote.weight,
+            }];
+            vote.timestamp = self.env().block_number();
+
+            self.votes.insert((proposal_id, voter), &vote);
+            self.proposals.insert(proposal_id, &proposal);
+
+            self.env().emit_event(VoteChanged {
+                proposal_id,
+                voter,
+                old_option,
+                new_option,
+                weight: vote.weight,
+            });
+
+            Ok(())
+        }
+
+        /// Withdraw the caller's vote while voting is open
+        ///
+        /// Delegated weight carried by the vote is released and can be cast again
+        /// when the delegate votes anew.
+        #[ink(message)]
+        pub fn revoke_vote(&mut self, proposal_id: u32) -> Result<()> {
+            let voter = self.caller_account();
+
+            let mut proposal = self.open_proposal(proposal_id)?;
+            let vote = self.votes.get((proposal_id, voter))
+                .ok_or(Error::NotVoted)?;
+
+            for choice in &vote.choices {
+                let index = choice.option_index as usize;
+                proposal.vote_counts[index] = proposal.vote_counts[index].saturating_sub(choice.weight);
+            }
+            proposal.total_voters = proposal.total_voters
+                .saturating_sub(1)
+                .saturating_sub(vote.delegators.len() as u32);
+            proposal.turnout = proposal.turnout.saturating_sub(vote.weight);
+
+            for delegator in &vote.delegators {
+                self.delegated_votes.remove((proposal_id, *delegator));
+            }
+
+            if proposal.governance_params.voting_method == VotingMethod::RankedChoice {
+                self.remove_ranked_ballot(proposal_id, voter);
+            }
+
+            self.votes.remove((proposal_id, voter));
+            self.proposals.insert(proposal_id, &proposal);
+
+            self.env().emit_event(VoteRevoked {
+                proposal_id,
+                voter,
+                weight: vote.weight,
+            });
+
+            Ok(())
+        }
+
+        /// Update proposal status after voting period
+        ///
+        /// Queries and `execute_proposal` already see the outcome once voting has
+        /// closed; this persists it and emits `ProposalFinalized`.
+        #[ink(message)]
+        pub fn update_proposal_status(&mut self, proposal_id: u32) -> Result<()> {
+            let mut proposal = self.proposals.get(proposal_id)
+                .ok_or(Error::ProposalNotFound)?;
+
+            self.finalize(&mut proposal);
+            Ok(())
+        }
+
+        /// Cancel a proposal before it is executed
+        ///
+        /// The proposer may withdraw a proposal until the first vote or commitment
+        /// is made. The owner and the guardian may cancel any proposal that is
+        /// still open or has passed but not yet been executed. Past its execution
+        /// deadline a passed proposal can only be expired (`ProposalExpired`).
+        #[ink(message)]
+        pub fn cancel_proposal(&mut self, proposal_id: u32) -> Result<()> {
+            let caller = self.caller_account();
+            let current_block = self.env().block_number();
+
+            let mut proposal = self.proposals.get(proposal_id)
+                .ok_or(Error::ProposalNotFound)?;
+            self.finalize(&mut proposal);
+
+            if !matches!(
+                proposal.status,
+                ProposalStatus::Active | ProposalStatus::Revealing | ProposalStatus::Passed
+            ) {
+                return Err(Error::ProposalNotCancellable);
+            }
+
+            // A veto after the execution window would mislabel an expired proposal
+            if proposal.status == ProposalStatus::Passed && current_block > proposal.execution_deadline() {
+                return Err(Error::ProposalExpired);
+            }
+
+            let privileged = self.has_role(Role::Admin, caller) || self.has_role(Role::Guardian, caller);
+            if !privileged {
+                if caller != proposal.proposer {
+                    return Err(Error::NotAuthorized);
+                }
+                let has_votes = proposal.total_voters > 0
+                    || self.commitment_counts.get(proposal_id).unwrap_or(0) > 0;
+                if has_votes {
+                    return Err(Error::ProposalHasVotes);
+                }
+            }
+
+            let old_status = proposal.status.clone();
+            proposal.rejection_reason = if privileged && old_status == ProposalStatus::Passed {
+                Some(RejectionReason::Vetoed)
+            } else {
+                Some(RejectionReason::Cancelled)
+            };
+            proposal.status = ProposalStatus::Cancelled;
+            self.proposals.insert(proposal_id, &proposal);
+            self.emit_status_changed(&proposal, old_status);
+
+            self.env().emit_event(ProposalCancelled {
+                proposal_id,
+                cancelled_by: caller,
+            });
+
+            Ok(())
+        }
+
+        /// Execute a passed proposal
+        #[ink(message)]
+        pub fn execute_proposal(&mut self, proposal_id: u32) -> Result<()> {
+            let current_block = self.env().block_number();
+
+            let mut proposal = self.proposals.get(proposal_id)
+                .ok_or(Error::ProposalNotFound)?;
+
+            // Finalize the tally if nobody has called `update_proposal_status` yet
+            self.finalize(&mut proposal);
+
+            // Check if proposal is in passed status
+            if proposal.status != ProposalStatus::Passed {
+                return Err(Error::ProposalNotReadyForExecution);
+            }
+
+            // Check if execution delay has passed
+            if current_block < proposal.execution_time {
+                return Err(Error::ProposalNotReadyForExecution);
+            }
+
+            // Past the grace period the proposal can only be expired
+            if current_block > proposal.execution_deadline() {
+                return Err(Error::ProposalExpired);
+            }
+
+            proposal.status = ProposalStatus::Executed;
+            self.proposals.insert(proposal_id, &proposal);
+            self.emit_status_changed(&proposal, ProposalStatus::Passed);
+
+            let mut payout = None;
+            match &proposal.payload {
+                ProposalPayload::Signal => {}
+                ProposalPayload::Spend(spend) => {
+                    self.pay_out(proposal_id, spend, current_block)?;
+                    payout = Some(spend.clone());
+                }
+                ProposalPayload::Calls(actions) => {
+                    self.dispatch_actions(proposal_id, actions)?;
+                }
+                ProposalPayload::Configure(change) => {
+                    self.apply_config_change(change.clone())?;
+                }
+                ProposalPayload::Upgrade(code_hash) => {
+                    // The new code runs from the next call on; `migrate` then updates storage
+                    self.env()
+                        .set_code_hash(code_hash)
+                        .map_err(|_| Error::UpgradeFailed)?;
+                    self.env().emit_event(CodeUpgraded {
+                        proposal_id,
+                        code_hash: *code_hash,
+                    });
+                }
+            }
+
+            self.env().emit_event(ProposalExecuted {
+                proposal_id,
+                status: ProposalStatus::Executed,
+                payout,
+            });
+
+            Ok(())
+        }
+
+        /// Mark a passed proposal that missed its execution window as expired
+        ///
+        /// Callable by anyone once the grace period after `execution_time` is over.
+        #[ink(message)]
+        pub fn expire_proposal(&mut self, proposal_id: u32) -> Result<()> {
+            let mut proposal = self.proposals.get(proposal_id)
+                .ok_or(Error::ProposalNotFound)?;
+            self.finalize(&mut proposal);
+
+            let execution_deadline = proposal.execution_deadline();
+            if proposal.status != ProposalStatus::Passed
+                || self.env().block_number() <= execution_deadline
+            {
+                return Err(Error::ProposalNotExpired);
+            }
+
+            proposal.status = ProposalStatus::Expired;
+            self.proposals.insert(proposal_id, &proposal);
+            self.emit_status_changed(&proposal, ProposalStatus::Passed);
+
+            self.env().emit_event(ProposalExpired {
+                proposal_id,
+                execution_deadline,
+            });
+
+            Ok(())
+        }
+
+        /// Bring storage written by older code up to `STORAGE_VERSION`
+        ///
+        /// Called by anyone after an upgrade; does nothing when storage is current.
+        /// Only `Lazy` and `Mapping` cells can change type: a migration reads the old
+        /// type from the cell's key with `ink::env::get_contract_storage` and writes the new one.
+        /// A type embedded in other stored values, like `DurationBounds` in a proposal's
+        /// `ConfigChange::SetDurationBounds`, changes their encoding too; cells a migration
+        /// does not rewrite must keep decoding with the old type.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<()> {
+            let from_version = self.storage_version;
+            if from_version >= STORAGE_VERSION {
+                return Ok(());
+            }
+
+            // Migrations for each layout change are applied here, oldest first
+
+            self.storage_version = STORAGE_VERSION;
+            self.env().emit_event(Migrated {
+                from_version,
+                to_version: STORAGE_VERSION,
+            });
+
+            Ok(())
+        }
+
+        /// Transfer the funds of an approved spend and record the payout
+        fn pay_out(&mut self, proposal_id: u32, spend: &TreasurySpend, current_block: u32) -> Result<()> {
+            if self.get_treasury_balance() < spend.amount {
+                return Err(Error::InsufficientTreasuryBalance);
+            }
+
+            self.env()
+                .transfer(spend.beneficiary, U256::from(spend.amount))
+                .map_err(|_| Error::TransferFailed)?;
+
+            self.payouts.insert(proposal_id, &Payout {
+                beneficiary: spend.beneficiary,
+                amount: spend.amount,
+                paid_at: current_block,
+            });
+            self.total_paid_out = self.total_paid_out.saturating_add(spend.amount);
+
+            Ok(())
+        }
+
+        /// Apply a configuration change approved by a Governance proposal
+        ///
+        /// Type policies are validated again, as the limits and role holders they were
+        /// checked against may have changed since the proposal was created.
+        fn apply_config_change(&mut self, change: ConfigChange) -> Result<()> {
+            match change {
+                ConfigChange::SetTypePolicy(proposal_type, policy) => {
+                    self.validate_type_policy(&proposal_type, &policy)?;
+                    self.type_policies.insert(proposal_type.clone(), &policy);
+                    self.env().emit_event(TypePolicyChanged { proposal_type, policy });
+                }
+                ConfigChange::SetDefaultParameters(params) => {
+                    self.default_params.set(&params);
+                    self.env().emit_event(DefaultParametersChanged { params });
+                }
+                ConfigChange::SetMembershipPolicy(policy) => {
+                    self.membership_policy = policy.clone();
+                    self.env().emit_event(MembershipPolicyChanged { policy });
+                }
+                ConfigChange::SetOwner(new_owner) => {
+                    let old_owner = self.owner;
+                    self.owner = new_owner;
+                    self.env().emit_event(OwnerChanged { old_owner, new_owner });
+                }
+                ConfigChange::SetGuardian(guardian) => {
+                    self.guardian = guardian;
+                    self.env().emit_event(GuardianChanged { guardian });
+                }
+                ConfigChange::SetDurationBounds(bounds) => {
+                    self.duration_bounds.set(&bounds);
+                    self.env().emit_event(DurationBoundsChanged { bounds });
+                }
+                ConfigChange::SetQuorumLimits(limits) => {
+                    self.quorum_limits.set(&limits);
+                    self.env().emit_event(QuorumLimitsChanged { limits });
+                }
+                ConfigChange::SetExecutionGracePeriod(blocks) => {
+                    self.execution_grace_period = blocks;
+                    self.env().emit_event(ExecutionGracePeriodChanged { blocks });
+                }
+            }
+            Ok(())
+        }
+
+        /// Dispatch every action of a proposal, recording the outcome of each call
+        ///
+        /// A failing call does not abort the remaining actions; its failure is
+        /// recorded in the results instead.
+        fn dispatch_actions(&mut self, proposal_id: u32, actions: &[ProposalAction]) -> Result<()> {
+            let total_value = actions
+                .iter()
+                .fold(0u128, |acc, action| acc.saturating_add(action.transferred_value));
+            if self.get_treasury_balance() < total_value {
+                return Err(Error::InsufficientTreasuryBalance);
+            }
+
+            let mut results = Vec::with_capacity(actions.len());
+            for action in actions {
+                let call_result = build_call::<ink::env::DefaultEnvironment>()
+                    .call(action.callee)
+                    .transferred_value(U256::from(action.transferred_value))
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(action.selector))
+                            .push_arg(CallInput(&action.input)),
+                    )
+                    .returns::<CallOutput>()
+                    .try_invoke();
+
+                let result = match call_result {
+                    Ok(Ok(CallOutput(output))) => ActionResult { success: true, output },
+                    _ => ActionResult { success: false, output: Vec::new() },
+                };
+                results.push(result);
+            }
+
+            self.action_results.insert(proposal_id, &results);
+            Ok(())
+        }
+
+        // ========== QUERY FUNCTIONS ==========
+
+        /// Get a specific proposal
+        ///
+        /// The status is the effective one: a proposal whose voting has closed shows
+        /// its outcome even before `update_proposal_status` is called.
+        #[ink(message)]
+        pub fn get_proposal(&self, proposal_id: u32) -> Option<Proposal> {
+            self.effective_proposal(proposal_id)
+        }
+
+        /// Get all proposal IDs
+        #[ink(message)]
+        pub fn get_all_proposal_ids(&self) -> Vec<u32> {
+            self.proposal_ids.clone()
+        }
+
+        /// Get user's vote on a proposal
+        #[ink(message)]
+        pub fn get_user_vote(&self, proposal_id: u32, user: AccountId) -> Option<Vote> {
+            self.votes.get((proposal_id, user))
+        }
+
+        /// Get total registered voters
+        #[ink(message)]
+        pub fn get_total_voters(&self) -> u32 {
+            self.total_voters
+        }
+
+        /// Check if an account is a registered voter
+        #[ink(message)]
+        pub fn is_registered(&self, account: AccountId) -> bool {
+            self.registered_voters.get(account).is_some()
+        }
+
+        /// Check if an account has a registration awaiting owner approval
+        #[ink(message)]
+        pub fn is_pending_voter(&self, account: AccountId) -> bool {
+            self.pending_voters.get(account).is_some()
+        }
+
+        /// Get the current membership policy
+        #[ink(message)]
+        pub fn get_membership_policy(&self) -> MembershipPolicy {
+            self.membership_policy.clone()
+        }
+
+        /// Check if an account holds a role, explicitly granted or implied
+        #[ink(message)]
+        pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+            let implied = match role {
+                Role::Admin => account == self.owner,
+                Role::Guardian => self.guardian == Some(account),
+                Role::Member => self.is_registered(account),
+                Role::Proposer => false,
+            };
+            implied || self.roles.contains((role, account))
+        }
+
+        /// Get the contract owner
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Get the layout version of the data in storage
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u32 {
+            self.storage_version
+        }
+
+        /// Get the parameters used by `create_proposal_with_defaults`
+        #[ink(message)]
+        pub fn get_default_params(&self) -> GovernanceParameters {
+            self.default_params.get().unwrap_or_default()
+        }
+
+        /// Get the guardian allowed to cancel proposals, if any
+        #[ink(message)]
+        pub fn get_guardian(&self) -> Option<AccountId> {
+            self.guardian
+        }
+
+        /// Get how many blocks passed proposals stay executable after their execution time
+        #[ink(message)]
+        pub fn get_execution_grace_period(&self) -> u32 {
+            self.execution_grace_period
+        }
+
+        /// Get the limits proposal durations are validated against
+        #[ink(message)]
+        pub fn get_duration_bounds(&self) -> DurationBounds {
+            self.duration_bounds.get().unwrap_or_default()
+        }
+
+        /// Get the quorum range proposals may choose and the absolute vote floor
+        #[ink(message)]
+        pub fn get_quorum_limits(&self) -> QuorumLimits {
+            self.quorum_limits.get().unwrap_or_default()
+        }
+
+        /// Get the policy proposals of a type must satisfy
+        #[ink(message)]
+        pub fn get_type_policy(&self, proposal_type: ProposalType) -> TypePolicy {
+            self.type_policies.get(proposal_type).unwrap_or_default()
+        }
+
+        /// Get the account an account delegates its voting weight to
+        #[ink(message)]
+        pub fn get_delegation(&self, account: AccountId) -> Option<AccountId> {
+            self.delegations.get(account)
+        }
+
+        /// Get the accounts delegating directly to an account
+        #[ink(message)]
+        pub fn get_delegators(&self, account: AccountId) -> Vec<AccountId> {
+            self.delegators.get(account).unwrap_or_default()
+        }
+
+        /// Get how many accounts delegate to an account, directly or through others
+        #[ink(message)]
+        pub fn get_delegator_count(&self, account: AccountId) -> u32 {
+            self.delegator_counts.get(account).unwrap_or(0)
+        }
+
+        /// Get the account that ultimately casts an account's vote, following delegations
+        #[ink(message)]
+        pub fn resolve_delegate(&self, account: AccountId) -> AccountId {
+            let mut current = account;
+            for _ in 0..MAX_DELEGATION_DEPTH {
+                match self.delegations.get(current) {
+                    Some(next) => current = next,
+                    None => break,
+                }
+            }
+            current
+        }
+
+        /// Get the nonce the next signed ballot of a voter must carry
+        #[ink(message)]
+        pub fn get_vote_nonce(&self, voter: AccountId) -> u64 {
+            self.vote_nonces.get(voter).unwrap_or(0)
+        }
+
+        /// Get the digest a voter signs to vote off-chain
+        ///
+        /// Keccak-256 of the SCALE-encoded `(contract_address, proposal_id, option_index, nonce)`.
+        #[ink(message)]
+        pub fn signed_vote_digest(&self, proposal_id: u32, option_index: u32, nonce: u64) -> [u8; 32] {
+            self.env()
+                .hash_encoded::<Keccak256, _>(&(self.env().address(), proposal_id, option_index, nonce))
+        }
+
+        /// Get the governance token votes are weighted by, if any
+        #[ink(message)]
+        pub fn get_governance_token(&self) -> Option<H160> {
+            self.governance_token
+        }
+
+        /// Check if proposal reached quorum
+        #[ink(message)]
+        pub fn has_reached_quorum(&self, proposal_id: u32) -> bool {
+            if let Some(proposal) = self.proposals.get(proposal_id) {
+                proposal.turnout >= proposal.required_quorum_votes()
+            } else {
+                false
+            }
+        }
+
+        /// Get proposal results
+        #[ink(message)]
+        pub fn get_proposal_results(&self, proposal_id: u32) -> Option<(Vec<u128>, bool)> {
+            if let Some(proposal) = self.proposals.get(proposal_id) {
+                let reached_quorum = self.has_reached_quorum(proposal_id);
+                Some((proposal.vote_counts, reached_quorum))
+            } else {
+                None
+            }
+        }
+
+        /// Get voting options for a proposal
+        #[ink(message)]
+        pub fn get_voting_options(&self, proposal_id: u32) -> Option<Vec<String>> {
+            if let Some(proposal) = self.proposals.get(proposal_id) {
+                Some(proposal.voting_options.options)
+            } else {
+                None
+            }
+        }
+
+        /// Get detailed results with option names
+        #[ink(message)]
+        pub fn get_detailed_results(&self, proposal_id: u32) -> Option<Vec<(String, u128)>> {
+            if let Some(proposal) = self.proposals.get(proposal_id) {
+                let mut results = Vec::new();
+                for (i, option) in proposal.voting_options.options.iter().enumerate() {
+                    results.push((option.clone(), proposal.vote_counts[i]));
                 }
                 Some(results)
             } else {
-                None
+                None
+            }
+        }
+
+        /// Get the winning option
+        #[ink(message)]
+        pub fn get_winning_option(&self, proposal_id: u32) -> Option<(String, u128)> {
+            if let Some(proposal) = self.proposals.get(proposal_id) {
+                // Ranked proposals are won on the final runoff round once tallied
+                let counts = self.get_runoff_rounds(proposal_id)
+                    .and_then(|rounds| rounds.last().map(|round| round.tallies.clone()))
+                    .unwrap_or(proposal.vote_counts);
+
+                let mut max_votes = 0u128;
+                let mut winning_index = 0usize;
+
+                for (i, &votes) in counts.iter().enumerate() {
+                    if votes > max_votes {
+                        max_votes = votes;
+                        winning_index = i;
+                    }
+                }
+
+                if max_votes > 0 {
+                    Some((
+                        proposal.voting_options.options[winning_index].clone(),
+                        max_votes,
+                    ))
+                } else {
+                    None
+                }
+            } else {
+                None
+            }
+        }
+
+        /// Get the options that won a passed proposal with their vote counts
+        #[ink(message)]
+        pub fn get_winning_options(&self, proposal_id: u32) -> Option<Vec<(String, u128)>> {
+            let proposal = self.effective_proposal(proposal_id)?;
+            let winners = proposal.winning_options
+                .iter()
+                .map(|&index| {
+                    let index = index as usize;
+                    (proposal.voting_options.options[index].clone(), proposal.vote_counts[index])
+                })
+                .collect();
+            Some(winners)
+        }
+
+        /// Get the round-by-round instant-runoff tally of a finalized ranked-choice proposal
+        #[ink(message)]
+        pub fn get_runoff_rounds(&self, proposal_id: u32) -> Option<Vec<RunoffRound>> {
+            if let Some(rounds) = self.runoff_rounds.get(proposal_id) {
+                return Some(rounds);
+            }
+            // Voting may have closed without the tally being persisted yet
+            let mut proposal = self.proposals.get(proposal_id)?;
+            self.settle(&mut proposal)?.runoff_rounds
+        }
+
+        /// Get the native balance currently held by the treasury
+        #[ink(message)]
+        pub fn get_treasury_balance(&self) -> Balance {
+            self.env().balance().as_u128()
+        }
+
+        /// Get the total amount an account has deposited
+        #[ink(message)]
+        pub fn get_contribution(&self, depositor: AccountId) -> Balance {
+            self.contributions.get(depositor).unwrap_or(0)
+        }
+
+        /// Get the total amount ever deposited into the treasury
+        #[ink(message)]
+        pub fn get_total_deposited(&self) -> Balance {
+            self.total_deposited
+        }
+
+        /// Get the payout made by an executed spending proposal
+        #[ink(message)]
+        pub fn get_payout(&self, proposal_id: u32) -> Option<Payout> {
+            self.payouts.get(proposal_id)
+        }
+
+        /// Get the total amount paid out by executed proposals
+        #[ink(message)]
+        pub fn get_total_paid_out(&self) -> Balance {
+            self.total_paid_out
+        }
+
+        /// Get the per-action outcomes of an executed proposal
+        #[ink(message)]
+        pub fn get_action_results(&self, proposal_id: u32) -> Option<Vec<ActionResult>> {
+            self.action_results.get(proposal_id)
+        }
+
+        /// Get contract statistics
+        #[ink(message)]
+        pub fn get_stats(&self) -> (u32, u32, u32) {
+            let total = self.proposal_ids.len() as u32;
+            let mut active = 0u32;
+            let mut executed = 0u32;
+
+            for &id in &self.proposal_ids {
+                if let Some(proposal) = self.effective_proposal(id) {
+                    match proposal.status {
+                        ProposalStatus::Active | ProposalStatus::Revealing => active = active.saturating_add(1),
+                        ProposalStatus::Executed => executed = executed.saturating_add(1),
+                        _ => {}
+                    }
+                }
+            }
+
+            (total, active, executed)
+        }
+
+        // ========== INTERNAL HELPERS ==========
+
+        /// Convert the caller's H160 address into the padded `AccountId` used for bookkeeping
+        fn caller_account(&self) -> AccountId {
+            Self::to_account_id(self.env().caller())
+        }
+
+        /// Convert an H160 address into the padded `AccountId` used for bookkeeping
+        fn to_account_id(address: H160) -> AccountId {
+            let mut bytes = [0u8; 32];
+            bytes[12..32].copy_from_slice(address.as_ref());
+            AccountId::from(bytes)
+        }
+
+        /// Cast a single-choice vote on behalf of `voter`
+        fn cast_vote(&mut self, voter: AccountId, proposal_id: u32, option_index: u32) -> Result<()> {
+            let (proposal, delegators, weight) = self.prepare_ballot(voter, proposal_id)?;
+
+            if proposal.governance_params.voting_method != VotingMethod::SingleChoice {
+                return Err(Error::InvalidVotingMethod);
+            }
+
+            // Validate option index
+            if option_index as usize >= proposal.voting_options.options.len() {
+                return Err(Error::InvalidProposal);
+            }
+
+            // Get option text
+            let option_text = proposal.voting_options.options[option_index as usize].clone();
+
+            let choices = vec![VoteChoice {
+                option_index,
+                option_text,
+                weight,
+            }];
+
+            self.record_ballot(proposal, voter, choices, weight, delegators, 0);
+            Ok(())
+        }
+
+        /// Add an account to the electorate
+        fn add_voter(&mut self, voter: AccountId) {
+            self.registered_voters.insert(voter, &true);
+            self.total_voters = self.total_voters.saturating_add(1);
+
+            self.env().emit_event(VoterRegistered { voter });
+        }
+
+        /// Convert a padded `AccountId` back into the H160 address it was derived from
+        fn account_address(account: AccountId) -> H160 {
+            let bytes: &[u8; 32] = account.as_ref();
+            H160::from_slice(&bytes[12..32])
+        }
+
+        /// Check that `voter` may cast a ballot on a proposal
+        ///
+        /// Returns the proposal, the delegators whose weight the ballot carries and
+        /// the total weight (own plus delegated).
+        fn prepare_ballot(&self, voter: AccountId, proposal_id: u32) -> Result<(Proposal, Vec<AccountId>, u128)> {
+            self.ensure_member(voter)?;
+
+            let proposal = self.open_proposal(proposal_id)?;
+            self.ensure_payload_allowed(&proposal.payload)?;
+
+            // Check if already voted, directly or through a delegate
+            if self.votes.contains((proposal_id, voter))
+                || self.delegated_votes.contains((proposal_id, voter))
+            {
+                return Err(Error::AlreadyVoted);
+            }
+
+            // Delegated voting power is cast by the delegate
+            if self.delegations.contains(voter) {
+                return Err(Error::VoteDelegated);
+            }
+
+            // Own weight plus the weight of everyone delegating to this voter
+            let (delegators, delegated_weight) = self.collect_delegators(&proposal, voter)?;
+            let weight = self.base_weight(voter, proposal.snapshot_block)?.saturating_add(delegated_weight);
+            if weight == 0 {
+                return Err(Error::NoVotingPower);
+            }
+
+            Ok((proposal, delegators, weight))
+        }
+
+        /// Load a proposal with the status it effectively has at the current block
+        fn effective_proposal(&self, proposal_id: u32) -> Option<Proposal> {
+            let mut proposal = self.proposals.get(proposal_id)?;
+            self.settle(&mut proposal);
+
+            if proposal.status == ProposalStatus::Passed
+                && self.env().block_number() > proposal.execution_deadline()
+            {
+                proposal.status = ProposalStatus::Expired;
+            }
+
+            Some(proposal)
+        }
+
+        /// Apply the status transitions due since voting closed, without persisting them
+        ///
+        /// Returns the tally when it moves the proposal to `Passed` or `Rejected`.
+        fn settle(&self, proposal: &mut Proposal) -> Option<Tally> {
+            let current_block = self.env().block_number();
+
+            if !matches!(proposal.status, ProposalStatus::Active | ProposalStatus::Revealing)
+                || current_block <= proposal.voting_end
+            {
+                return None;
+            }
+
+            // Commit-reveal proposals wait for the reveal window to close
+            if current_block <= proposal.reveal_end {
+                proposal.status = ProposalStatus::Revealing;
+                return None;
+            }
+
+            let tally = self.tally(proposal);
+            proposal.status = if tally.rejection.is_none() {
+                ProposalStatus::Passed
+            } else {
+                ProposalStatus::Rejected
+            };
+            proposal.winning_options = tally.winning_options.clone();
+            proposal.rejection_reason = tally.rejection.clone();
+            Some(tally)
+        }
+
+        /// Settle a proposal and persist the result, emitting `ProposalFinalized` once decided
+        fn finalize(&mut self, proposal: &mut Proposal) {
+            let previous_status = proposal.status.clone();
+            let tally = self.settle(proposal);
+            if proposal.status == previous_status {
+                return;
+            }
+
+            self.proposals.insert(proposal.id, proposal);
+            self.emit_status_changed(proposal, previous_status);
+
+            if let Some(tally) = tally {
+                if let Some(rounds) = &tally.runoff_rounds {
+                    self.runoff_rounds.insert(proposal.id, rounds);
+                }
+                self.env().emit_event(ProposalFinalized {
+                    proposal_id: proposal.id,
+                    passed: tally.rejection.is_none(),
+                    reason: tally.rejection,
+                });
+            }
+        }
+
+        /// Emit `ProposalStatusChanged` for a proposal that has just moved out of `old`
+        fn emit_status_changed(&self, proposal: &Proposal, old: ProposalStatus) {
+            self.env().emit_event(ProposalStatusChanged {
+                proposal_id: proposal.id,
+                old,
+                new: proposal.status.clone(),
+                reason: proposal.rejection_reason.clone(),
+                block: self.env().block_number(),
+            });
+        }
+
+        /// Count a closed proposal against its quorum and voting method
+        fn tally(&self, proposal: &Proposal) -> Tally {
+            let rejected = |reason| Tally {
+                rejection: Some(reason),
+                winning_options: Vec::new(),
+                runoff_rounds: None,
+            };
+
+            // Calculate quorum against the electorate snapshot
+            if proposal.turnout < proposal.required_quorum_votes() {
+                return rejected(RejectionReason::NoQuorum);
+            }
+
+            let threshold = &proposal.governance_params.approval_threshold;
+
+            // Ranked ballots are decided by instant runoff
+            if proposal.governance_params.voting_method == VotingMethod::RankedChoice {
+                let (rounds, winner) = self.instant_runoff(proposal);
+                // The winner must also clear the threshold in the final round
+                let rejection = match (winner, rounds.last()) {
+                    (Some(winner), Some(last)) => {
+                        let counted = last.tallies.iter().fold(0u128, |acc, &votes| acc.saturating_add(votes));
+                        if threshold.is_met(last.tallies[winner as usize], counted) {
+                            None
+                        } else {
+                            Some(RejectionReason::BelowThreshold)
+                        }
+                    }
+                    _ => Some(RejectionReason::Tie),
+                };
+                return Tally {
+                    winning_options: if rejection.is_none() { winner.into_iter().collect() } else { Vec::new() },
+                    rejection,
+                    runoff_rounds: Some(rounds),
+                };
+            }
+
+            // Approval proposals pass their top N options, each approved by the threshold share of voters
+            if let VotingMethod::Approval { winners } = proposal.governance_params.voting_method {
+                return match Self::top_options(&proposal.vote_counts, winners as usize) {
+                    Some(top) if top.iter().all(|&index| {
+                        threshold.is_met(proposal.vote_counts[index as usize], proposal.turnout)
+                    }) => Tally { rejection: None, winning_options: top, runoff_rounds: None },
+                    Some(_) => rejected(RejectionReason::BelowThreshold),
+                    None => rejected(RejectionReason::Tie),
+                };
+            }
+
+            // Binary proposals pass when for beats against by the threshold share
+            if proposal.voting_options.is_binary() {
+                let (Some(for_index), Some(against_index)) = (
+                    proposal.voting_options.position(OptionRole::For),
+                    proposal.voting_options.position(OptionRole::Against),
+                ) else {
+                    return rejected(RejectionReason::BelowThreshold);
+                };
+                let votes_for = proposal.vote_counts[for_index];
+                let votes_against = proposal.vote_counts[against_index];
+
+                if !threshold.is_met(votes_for, votes_for.saturating_add(votes_against)) {
+                    return rejected(if votes_for == votes_against && votes_for > 0 {
+                        RejectionReason::Tie
+                    } else {
+                        RejectionReason::BelowThreshold
+                    });
+                }
+
+                return Tally {
+                    rejection: None,
+                    winning_options: vec![for_index as u32],
+                    runoff_rounds: None,
+                };
+            }
+
+            // Find winning option (highest vote count), abstentions cannot win
+            let mut max_votes = 0u128;
+            let mut winning_index = 0usize;
+            let mut winning_count: u32 = 0;
+            let mut cast = 0u128;
+
+            for (i, &votes) in proposal.vote_counts.iter().enumerate() {
+                if proposal.voting_options.is_abstain(i) {
+                    continue;
+                }
+                cast = cast.saturating_add(votes);
+                if votes > max_votes {
+                    max_votes = votes;
+                    winning_index = i;
+                    winning_count = 1;
+                } else if votes == max_votes && votes > 0 {
+                    winning_count = winning_count.saturating_add(1);
+                }
+            }
+
+            // Handle ties (mark as rejected)
+            if winning_count > 1 {
+                return rejected(RejectionReason::Tie);
+            }
+
+            // The winner needs its share of the non-abstaining votes
+            if !threshold.is_met(max_votes, cast) {
+                return rejected(RejectionReason::BelowThreshold);
+            }
+
+            Tally {
+                rejection: None,
+                winning_options: vec![winning_index as u32],
+                runoff_rounds: None,
+            }
+        }
+
+        /// Fail with `NotAuthorized` unless the caller holds `role`
+        fn ensure_role(&self, role: Role) -> Result<()> {
+            if !self.has_role(role, self.caller_account()) {
+                return Err(Error::NotAuthorized);
+            }
+            Ok(())
+        }
+
+        /// Load a proposal that is still accepting votes
+        fn open_proposal(&self, proposal_id: u32) -> Result<Proposal> {
+            let proposal = self.proposals.get(proposal_id)
+                .ok_or(Error::ProposalNotFound)?;
+
+            if proposal.status != ProposalStatus::Active {
+                return Err(Error::ProposalNotActive);
+            }
+
+            if self.env().block_number() > proposal.voting_end {
+                return Err(Error::VotingPeriodEnded);
+            }
+
+            Ok(proposal)
+        }
+
+        /// Store a validated ballot, add its choices to the tally and emit `VoteCast` per choice
+        fn record_ballot(
+            &mut self,
+            mut proposal: Proposal,
+            voter: AccountId,
+            choices: Vec<VoteChoice>,
+            weight: u128,
+            delegators: Vec<AccountId>,
+            credits_spent: u128,
+        ) {
+            let proposal_id = proposal.id;
+
+            for delegator in &delegators {
+                self.delegated_votes.insert((proposal_id, *delegator), &voter);
+            }
+
+            // Update vote counts
+            for choice in &choices {
+                let index = choice.option_index as usize;
+                proposal.vote_counts[index] = proposal.vote_counts[index].saturating_add(choice.weight);
+            }
+
+            proposal.total_voters = proposal.total_voters
+                .saturating_add(1)
+                .saturating_add(delegators.len() as u32);
+            proposal.turnout = proposal.turnout.saturating_add(weight);
+
+            // Store vote and update proposal
+            let vote = Vote {
+                voter,
+                choices: choices.clone(),
+                timestamp: self.env().block_number(),
+                weight,
+                delegators,
+                credits_spent,
+            };
+            self.votes.insert((proposal_id, voter), &vote);
+            self.proposals.insert(proposal_id, &proposal);
+
+            for choice in choices {
+                self.env().emit_event(VoteCast {
+                    proposal_id,
+                    voter,
+                    option_index: choice.option_index,
+                    option_text: choice.option_text,
+                    weight: choice.weight,
+                });
+            }
+        }
+
+        /// Pick the `count` options with the most votes, most votes first
+        ///
+        /// Returns `None` if one of them has no votes or the cut-off is tied.
+        fn top_options(vote_counts: &[u128], count: usize) -> Option<Vec<u32>> {
+            let mut ranked: Vec<usize> = (0..vote_counts.len()).collect();
+            ranked.sort_by(|&a, &b| vote_counts[b].cmp(&vote_counts[a]));
+
+            let last = *ranked.get(count.checked_sub(1)?)?;
+            if vote_counts[last] == 0 {
+                return None;
+            }
+            if let Some(&next) = ranked.get(count) {
+                if vote_counts[next] == vote_counts[last] {
+                    return None;
+                }
+            }
+
+            Some(ranked.into_iter().take(count).map(|index| index as u32).collect())
+        }
+
+        /// Run an instant-runoff tally over a proposal's ranked ballots
+        ///
+        /// Each round counts every ballot for its highest-ranked continuing option. An
+        /// option holding more than half of the counted weight wins; otherwise the option
+        /// with the least weight is eliminated, the later-listed one on a tie for last.
+        /// If all continuing options are tied, or no ballot counts, there is no winner.
+        fn instant_runoff(&self, proposal: &Proposal) -> (Vec<RunoffRound>, Option<u32>) {
+            let ballots: Vec<RankedBallot> = (0..self.ranked_ballot_counts.get(proposal.id).unwrap_or(0))
+                .filter_map(|position| self.ranked_ballots.get((proposal.id, position)))
+                .collect();
+            let option_count = proposal.voting_options.options.len();
+            let mut continuing = vec![true; option_count];
+            let mut rounds = Vec::new();
+
+            loop {
+                let mut tallies = vec![0u128; option_count];
+                for ballot in &ballots {
+                    if let Some(&choice) = ballot.ranking.iter().find(|&&index| continuing[index as usize]) {
+                        tallies[choice as usize] = tallies[choice as usize].saturating_add(ballot.weight);
+                    }
+                }
+                let total: u128 = tallies.iter().sum();
+
+                let leader = (0..option_count)
+                    .filter(|&i| continuing[i])
+                    .max_by_key(|&i| tallies[i]);
+                if let Some(leader) = leader {
+                    if total > 0 && tallies[leader].saturating_mul(2) > total {
+                        rounds.push(RunoffRound { tallies, eliminated: None });
+                        return (rounds, Some(leader as u32));
+                    }
+                }
+
+                // `min_by_key` keeps the first minimum, so iterating in reverse picks the later-listed option
+                let Some(lowest) = (0..option_count)
+                    .rev()
+                    .filter(|&i| continuing[i])
+                    .min_by_key(|&i| tallies[i])
+                else {
+                    return (rounds, None);
+                };
+                let all_tied = (0..option_count)
+                    .filter(|&i| continuing[i])
+                    .all(|i| tallies[i] == tallies[lowest]);
+                if total == 0 || all_tied {
+                    rounds.push(RunoffRound { tallies, eliminated: None });
+                    return (rounds, None);
+                }
+
+                continuing[lowest] = false;
+                rounds.push(RunoffRound { tallies, eliminated: Some(lowest as u32) });
+            }
+        }
+
+        /// Own voting weight of an account: its token balance at `snapshot_block` if a
+        /// governance token is set, else 1
+        fn base_weight(&self, account: AccountId, snapshot_block: u32) -> Result<u128> {
+            match self.governance_token {
+                Some(token) => self.token_balance_at(token, Self::account_address(account), snapshot_block),
+                None => Ok(1),
+            }
+        }
+
+        /// Collect the delegators whose weight flows to `delegate` on a proposal
+        ///
+        /// Delegations are followed transitively up to `MAX_DELEGATION_DEPTH` levels.
+        /// Delegators who already voted, whose weight was already cast, or who hold an
+        /// unrevealed commitment of their own are skipped.
+        fn collect_delegators(&self, proposal: &Proposal, delegate: AccountId) -> Result<(Vec<AccountId>, u128)> {
+            let proposal_id = proposal.id;
+            let mut collected = Vec::new();
+            let mut weight = 0u128;
+            let mut frontier = vec![delegate];
+
+            for _ in 0..MAX_DELEGATION_DEPTH {
+                let mut next = Vec::new();
+                for account in &frontier {
+                    for delegator in self.delegators.get(account).unwrap_or_default() {
+                        if self.votes.contains((proposal_id, delegator))
+                            || self.delegated_votes.contains((proposal_id, delegator))
+                            || self.commitments.contains((proposal_id, delegator))
+                        {
+                            continue;
+                        }
+                        weight = weight.saturating_add(self.base_weight(delegator, proposal.snapshot_block)?);
+                        collected.push(delegator);
+                        next.push(delegator);
+                    }
+                }
+                if next.is_empty() {
+                    break;
+                }
+                frontier = next;
+            }
+
+            Ok((collected, weight))
+        }
+
+        /// Remove a voter's ranked ballot, moving the last ballot into its position
+        fn remove_ranked_ballot(&mut self, proposal_id: u32, voter: AccountId) {
+            let Some(position) = self.ranked_ballot_positions.take((proposal_id, voter)) else {
+                return;
+            };
+            let last = self.ranked_ballot_counts.get(proposal_id).unwrap_or(1).saturating_sub(1);
+
+            if position != last {
+                if let Some(moved) = self.ranked_ballots.get((proposal_id, last)) {
+                    self.ranked_ballot_positions.insert((proposal_id, moved.voter), &position);
+                    self.ranked_ballots.insert((proposal_id, position), &moved);
+                }
+            }
+            self.ranked_ballots.remove((proposal_id, last));
+            self.ranked_ballot_counts.insert(proposal_id, &last);
+        }
+
+        /// `account` followed by the accounts it delegates to, nearest first
+        fn delegation_chain(&self, account: AccountId) -> Vec<AccountId> {
+            let mut chain = vec![account];
+            let mut current = account;
+            while chain.len() <= MAX_DELEGATION_DEPTH as usize {
+                match self.delegations.get(current) {
+                    Some(next) => {
+                        chain.push(next);
+                        current = next;
+                    }
+                    None => break,
+                }
+            }
+            chain
+        }
+
+        /// Add or remove `count` delegators on `account` and every account above it
+        fn update_delegator_counts(&mut self, account: AccountId, count: u32, add: bool) {
+            for ancestor in self.delegation_chain(account) {
+                let current = self.delegator_counts.get(ancestor).unwrap_or(0);
+                let updated = if add { current.saturating_add(count) } else { current.saturating_sub(count) };
+                if updated == 0 {
+                    self.delegator_counts.remove(ancestor);
+                } else {
+                    self.delegator_counts.insert(ancestor, &updated);
+                }
+            }
+        }
+
+        /// Length of the longest delegation chain ending at `account`
+        fn delegation_height(&self, account: AccountId) -> u32 {
+            let mut height = 0u32;
+            let mut frontier = vec![account];
+
+            while height <= MAX_DELEGATION_DEPTH {
+                let next: Vec<AccountId> = frontier
+                    .iter()
+                    .flat_map(|account| self.delegators.get(account).unwrap_or_default())
+                    .collect();
+                if next.is_empty() {
+                    break;
+                }
+                height = height.saturating_add(1);
+                frontier = next;
+            }
+
+            height
+        }
+
+        /// Remove `delegator` from the direct delegators of `delegate`
+        fn remove_delegator(&mut self, delegate: AccountId, delegator: AccountId) {
+            let mut delegators = self.delegators.get(delegate).unwrap_or_default();
+            delegators.retain(|account| *account != delegator);
+            if delegators.is_empty() {
+                self.delegators.remove(delegate);
+            } else {
+                self.delegators.insert(delegate, &delegators);
+            }
+        }
+
+        /// Query `PSP22Checkpoints::balance_of_at` on the governance token
+        fn token_balance_at(&self, token: H160, owner: H160, block: u32) -> Result<Balance> {
+            build_call::<ink::env::DefaultEnvironment>()
+                .call(token)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22Checkpoints::balance_of_at")))
+                        .push_arg(owner)
+                        .push_arg(block),
+                )
+                .returns::<Balance>()
+                .try_invoke()
+                .map_err(|_| Error::TokenQueryFailed)?
+                .map_err(|_| Error::TokenQueryFailed)
+        }
+
+        /// Query `PSP22Checkpoints::total_supply_at` on the governance token
+        fn token_total_supply_at(&self, token: H160, block: u32) -> Result<Balance> {
+            build_call::<ink::env::DefaultEnvironment>()
+                .call(token)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22Checkpoints::total_supply_at")))
+                        .push_arg(block),
+                )
+                .returns::<Balance>()
+                .try_invoke()
+                .map_err(|_| Error::TokenQueryFailed)?
+                .map_err(|_| Error::TokenQueryFailed)
+        }
+
+        /// Check that proposals could still satisfy a type policy
+        ///
+        /// Minimums beyond the duration bounds or quorum limits admit no proposal at all.
+        /// Policies only change through Governance proposals, so a Governance policy must
+        /// also list a role someone holds, or the policies could never change again.
+        fn validate_type_policy(&self, proposal_type: &ProposalType, policy: &TypePolicy) -> Result<()> {
+            let bounds = self.get_duration_bounds();
+            if policy.min_voting_period > bounds.max_voting_period
+                || policy.min_execution_delay > bounds.max_execution_delay
+            {
+                return Err(Error::InvalidDuration);
+            }
+            if policy.min_quorum_basis_points > self.get_quorum_limits().max_basis_points {
+                return Err(Error::InvalidQuorum);
+            }
+            if *proposal_type == ProposalType::Governance
+                && !policy.allowed_proposers.is_empty()
+                && !policy.allowed_proposers.iter().any(|role| self.role_has_holder(role))
+            {
+                return Err(Error::InvalidProposal);
+            }
+            Ok(())
+        }
+
+        /// Whether any account currently holds a role, implied or granted
+        fn role_has_holder(&self, role: &Role) -> bool {
+            let implied = match role {
+                Role::Admin => true,
+                Role::Guardian => self.guardian.is_some(),
+                Role::Member => self.total_voters > 0,
+                Role::Proposer => false,
+            };
+            implied || self.role_counts.get(role).unwrap_or(0) > 0
+        }
+
+        /// Fail with `PayloadNotAllowed` if a payload would act on-chain while anyone can vote
+        ///
+        /// Under `Open` membership without a governance token every address votes with weight 1,
+        /// so free addresses could pass spends, calls, reconfigurations and upgrades.
+        fn ensure_payload_allowed(&self, payload: &ProposalPayload) -> Result<()> {
+            if *payload != ProposalPayload::Signal
+                && self.membership_policy == MembershipPolicy::Open
+                && self.governance_token.is_none()
+            {
+                return Err(Error::PayloadNotAllowed);
+            }
+            Ok(())
+        }
+
+        /// Fail with `NotRegistered` if the membership policy excludes the account
+        fn ensure_member(&self, account: AccountId) -> Result<()> {
+            if self.membership_policy != MembershipPolicy::Open
+                && self.registered_voters.get(account).is_none()
+            {
+                return Err(Error::NotRegistered);
+            }
+            Ok(())
+        }
+    }
+
+    // ========== TESTS ==========
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Helper function to create default governance parameters
+        fn default_governance_params() -> GovernanceParameters {
+            GovernanceParameters {
+                voting_period: VotingPeriod::ThreeDays,
+                quorum_threshold: QuorumThreshold::Ten,
+                execution_delay: ExecutionDelay::OneDay,
+                voting_method: VotingMethod::SingleChoice,
+                approval_threshold: ApprovalThreshold::SimpleMajority,
+            }
+        }
+
+        /// Helper function to create default voting options
+        fn default_voting_options() -> VotingOptions {
+            VotingOptions {
+                options: vec![
+                    String::from("Approve"),
+                    String::from("Reject"),
+                    String::from("Abstain"),
+                ],
+                roles: vec![],
+            }
+        }
+
+        /// Helper function to create the Approve/Reject/Abstain options of a binary proposal
+        fn binary_voting_options() -> VotingOptions {
+            VotingOptions {
+                roles: vec![OptionRole::For, OptionRole::Against, OptionRole::Abstain],
+                ..default_voting_options()
+            }
+        }
+
+        /// Helper function to collect the `(old, new, reason)` status changes emitted for a proposal
+        fn status_changes(proposal_id: u32) -> Vec<(ProposalStatus, ProposalStatus, Option<RejectionReason>)> {
+            let signature = <ProposalStatusChanged as ink::env::Event>::SIGNATURE_TOPIC.unwrap();
+            ink::env::test::recorded_events()
+                .into_iter()
+                .filter(|event| event.topics.first().is_some_and(|topic| topic[..] == signature[..]))
+                .map(|event| <ProposalStatusChanged as ink::scale::Decode>::decode(&mut &event.data[..]).unwrap())
+                .filter(|event| event.proposal_id == proposal_id)
+                .map(|event| (event.old, event.new, event.reason))
+                .collect()
+        }
+
+        /// Helper function to create a registered-only contract with the default caller
+        /// registered, where proposals may carry payloads
+        fn registered_contract() -> TreasuryGovernance {
+            let mut contract = TreasuryGovernance::with_membership_policy(MembershipPolicy::RegisteredOnly);
+            contract.register_voter();
+            contract
+        }
+
+        /// Helper function to convert a test account into the padded `AccountId`
+        fn account_id(address: H160) -> AccountId {
+            let mut bytes = [0u8; 32];
+            bytes[12..32].copy_from_slice(address.as_ref());
+            AccountId::from(bytes)
+        }
+
+        #[ink::test]
+        fn test_1_new_contract_initialization() {
+            let contract = TreasuryGovernance::new();
+            assert_eq!(contract.get_total_voters(), 0);
+            assert_eq!(contract.get_all_proposal_ids().len(), 0);
+            let (total, active, executed) = contract.get_stats();
+            assert_eq!(total, 0);
+            assert_eq!(active, 0);
+            assert_eq!(executed, 0);
+        }
+
+        #[ink::test]
+        fn test_2_voter_registration() {
+            let mut contract = TreasuryGovernance::new();
+            contract.register_voter();
+            assert_eq!(contract.get_total_voters(), 1);
+            // Registering again should not increase count
+            contract.register_voter();
+            assert_eq!(contract.get_total_voters(), 1);
+        }
+
+        #[ink::test]
+        fn test_3_create_proposal_success() {
+            let mut contract = TreasuryGovernance::new();
+            let result = contract.create_proposal(
+                String::from("Treasury Fund Allocation"),
+                String::from("Allocate 1000 tokens for development"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                default_voting_options(),
+            );
+            assert!(result.is_ok());
+            let proposal_id = result.unwrap();
+            assert_eq!(proposal_id, 1);
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.title, String::from("Treasury Fund Allocation"));
+            assert_eq!(proposal.status, ProposalStatus::Active);
+            assert_eq!(proposal.vote_counts.len(), 3);
+        }
+
+        #[ink::test]
+        fn test_4_create_proposal_validation() {
+            let mut contract = TreasuryGovernance::new();
+
+            // Test empty options
+            let empty_options = VotingOptions { options: vec![], roles: vec![] };
+            let result = contract.create_proposal(
+                String::from("Invalid Proposal"),
+                String::from("Should fail"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                empty_options,
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            // Test too many options (>10)
+            let too_many_options = VotingOptions {
+                options: (1..=11).map(|i| format!("Option {}", i)).collect(),
+                roles: vec![],
+            };
+            let result = contract.create_proposal(
+                String::from("Invalid Proposal"),
+                String::from("Should fail"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                too_many_options,
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+        }
+
+        #[ink::test]
+        fn test_5_vote_success_and_tracking() {
+            let mut contract = TreasuryGovernance::new();
+            let proposal_id = contract.create_proposal(
+                String::from("Test Proposal"),
+                String::from("Test"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            // Vote on the proposal
+            let result = contract.vote(proposal_id, 0);
+            assert!(result.is_ok());
+
+            // Verify vote was recorded
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.vote_counts[0], 1);
+            assert_eq!(proposal.total_voters, 1);
+        }
+
+        #[ink::test]
+        fn test_6_vote_error_cases() {
+            let mut contract = TreasuryGovernance::new();
+            let proposal_id = contract.create_proposal(
+                String::from("Test Proposal"),
+                String::from("Test"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            // Test double voting
+            contract.vote(proposal_id, 0).unwrap();
+            assert_eq!(contract.vote(proposal_id, 1), Err(Error::AlreadyVoted));
+
+            // Test non-existent proposal
+            assert_eq!(contract.vote(999, 0), Err(Error::ProposalNotFound));
+
+            // Test invalid option index
+            let proposal_id2 = contract.create_proposal(
+                String::from("Test 2"),
+                String::from("Test"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+            assert_eq!(contract.vote(proposal_id2, 10), Err(Error::InvalidProposal));
+        }
+
+        #[ink::test]
+        fn test_7_governance_parameter_enums() {
+            // Test voting period conversions (6 second block time)
+            assert_eq!(VotingPeriod::ThreeDays.to_blocks(), 43_200);
+            assert_eq!(VotingPeriod::SevenDays.to_blocks(), 100_800);
+            assert_eq!(VotingPeriod::FourteenDays.to_blocks(), 201_600);
+            assert_eq!(VotingPeriod::ThirtyDays.to_blocks(), 432_000);
+
+            // Test quorum threshold percentages
+            assert_eq!(QuorumThreshold::Five.to_percentage(), 5);
+            assert_eq!(QuorumThreshold::Ten.to_percentage(), 10);
+            assert_eq!(QuorumThreshold::Twenty.to_percentage(), 20);
+            assert_eq!(QuorumThreshold::TwentyFive.to_percentage(), 25);
+
+            // Test execution delays
+            assert_eq!(ExecutionDelay::Immediately.to_blocks(), 0);
+            assert_eq!(ExecutionDelay::OneDay.to_blocks(), 14_400);
+            assert_eq!(ExecutionDelay::TwoDays.to_blocks(), 28_800);
+            assert_eq!(ExecutionDelay::SevenDays.to_blocks(), 100_800);
+        }
+
+        #[ink::test]
+        fn test_8_quorum_calculation() {
+            let mut contract = TreasuryGovernance::new();
+            contract.total_voters = 10; // Simulate 10 registered voters
+
+            let proposal_id = contract.create_proposal(
+                String::from("Test Proposal"),
+                String::from("Test"),
+                ProposalType::Treasury,
+                default_governance_params(), // 10% quorum
+                default_voting_options(),
+            ).unwrap();
+
+            // With 10 total voters and 10% quorum, need 1 vote minimum
+            assert_eq!(contract.has_reached_quorum(proposal_id), false);
+
+            contract.vote(proposal_id, 0).unwrap();
+            assert_eq!(contract.has_reached_quorum(proposal_id), true);
+        }
+
+        #[ink::test]
+        fn test_9_query_functions() {
+            let mut contract = TreasuryGovernance::new();
+            let proposal_id = contract.create_proposal(
+                String::from("Test Proposal"),
+                String::from("Test Description"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            contract.vote(proposal_id, 0).unwrap();
+
+            // Test get_voting_options
+            let options = contract.get_voting_options(proposal_id).unwrap();
+            assert_eq!(options.len(), 3);
+            assert_eq!(options[0], String::from("Approve"));
+
+            // Test get_detailed_results
+            let results = contract.get_detailed_results(proposal_id).unwrap();
+            assert_eq!(results[0], (String::from("Approve"), 1));
+            assert_eq!(results[1], (String::from("Reject"), 0));
+
+            // Test get_winning_option
+            let (winner, votes) = contract.get_winning_option(proposal_id).unwrap();
+            assert_eq!(winner, String::from("Approve"));
+            assert_eq!(votes, 1);
+
+            // Test get_proposal_results
+            let (vote_counts, _) = contract.get_proposal_results(proposal_id).unwrap();
+            assert_eq!(vote_counts[0], 1);
+        }
+
+        #[ink::test]
+        fn test_10_multiple_proposals_management() {
+            let mut contract = TreasuryGovernance::new();
+
+            let id1 = contract.create_proposal(
+                String::from("Proposal 1"),
+                String::from("First"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            let id2 = contract.create_proposal(
+                String::from("Proposal 2"),
+                String::from("Second"),
+                ProposalType::Governance,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            assert_eq!(id1, 1);
+            assert_eq!(id2, 2);
+
+            let all_ids = contract.get_all_proposal_ids();
+            assert_eq!(all_ids.len(), 2);
+
+            let (total, active, _) = contract.get_stats();
+            assert_eq!(total, 2);
+            assert_eq!(active, 2);
+        }
+
+        #[ink::test]
+        fn test_11_custom_voting_options() {
+            let mut contract = TreasuryGovernance::new();
+
+            let custom_options = VotingOptions {
+                options: vec![
+                    String::from("Strongly Agree"),
+                    String::from("Agree"),
+                    String::from("Neutral"),
+                    String::from("Disagree"),
+                    String::from("Strongly Disagree"),
+                ],
+                roles: vec![],
+            };
+
+            let proposal_id = contract.create_proposal(
+                String::from("Custom Options Test"),
+                String::from("Testing 5-point scale"),
+                ProposalType::Governance,
+                default_governance_params(),
+                custom_options,
+            ).unwrap();
+
+            let options = contract.get_voting_options(proposal_id).unwrap();
+            assert_eq!(options.len(), 5);
+            assert_eq!(options[0], String::from("Strongly Agree"));
+            assert_eq!(options[4], String::from("Strongly Disagree"));
+        }
+
+        #[ink::test]
+        fn test_12_proposal_types_and_execution() {
+            let mut contract = TreasuryGovernance::new();
+
+            // Test different proposal types
+            let treasury_id = contract.create_proposal(
+                String::from("Treasury Proposal"),
+                String::from("Fund allocation"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            let governance_id = contract.create_proposal(
+                String::from("Governance Change"),
+                String::from("Protocol update"),
+                ProposalType::Governance,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            let technical_id = contract.create_proposal(
+                String::from("Technical Upgrade"),
+                String::from("System enhancement"),
+                ProposalType::Technical,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            assert_eq!(contract.get_proposal(treasury_id).unwrap().proposal_type, ProposalType::Treasury);
+            assert_eq!(contract.get_proposal(governance_id).unwrap().proposal_type, ProposalType::Governance);
+            assert_eq!(contract.get_proposal(technical_id).unwrap().proposal_type, ProposalType::Technical);
+
+            // Test execution validation
+            let result = contract.execute_proposal(treasury_id);
+            assert_eq!(result, Err(Error::ProposalNotReadyForExecution));
+        }
+
+        #[ink::test]
+        fn test_13_treasury_deposits() {
+            let mut contract = TreasuryGovernance::new();
+            let accounts = ink::env::test::default_accounts();
+            ink::env::test::set_caller(accounts.bob);
+
+            // Zero-value deposits are rejected
+            ink::env::test::set_value_transferred(U256::zero());
+            assert_eq!(contract.deposit(), Err(Error::ZeroAmount));
+
+            // Deposits accumulate per depositor
+            ink::env::test::set_value_transferred(U256::from(500u128));
+            assert!(contract.deposit().is_ok());
+            ink::env::test::set_value_transferred(U256::from(250u128));
+            assert!(contract.deposit().is_ok());
+
+            assert_eq!(contract.get_contribution(account_id(accounts.bob)), 750);
+            assert_eq!(contract.get_total_deposited(), 750);
+        }
+
+        #[ink::test]
+        fn test_14_spending_proposals() {
+            let mut contract = registered_contract();
+            let accounts = ink::env::test::default_accounts();
+
+            // Spends are only allowed on Treasury proposals
+            let spend = ProposalPayload::Spend(TreasurySpend {
+                beneficiary: accounts.charlie,
+                amount: u128::MAX,
+            });
+            let result = contract.create_proposal_with_payload(
+                String::from("Misfiled Spend"),
+                String::from("Spend on a technical proposal"),
+                ProposalType::Technical,
+                default_governance_params(),
+                binary_voting_options(),
+                spend.clone(),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            // Zero-value spends are rejected
+            let result = contract.create_proposal_with_payload(
+                String::from("Empty Spend"),
+                String::from("Nothing to pay"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                binary_voting_options(),
+                ProposalPayload::Spend(TreasurySpend {
+                    beneficiary: accounts.charlie,
+                    amount: 0,
+                }),
+            );
+            assert_eq!(result, Err(Error::ZeroAmount));
+
+            // Spends need for/against options, so an ordinary "Reject" option cannot pay out
+            let result = contract.create_proposal_with_payload(
+                String::from("Plurality Spend"),
+                String::from("No for option to vote on"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                default_voting_options(),
+                spend.clone(),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            let proposal_id = contract.create_proposal_with_payload(
+                String::from("Grant"),
+                String::from("Pay charlie"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                binary_voting_options(),
+                spend.clone(),
+            ).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().payload, spend);
+
+            // Pass the proposal and try to execute a spend the treasury cannot cover
+            contract.vote(proposal_id, 0).unwrap();
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.voting_end + 1);
+            contract.update_proposal_status(proposal_id).unwrap();
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.execution_time);
+
+            assert_eq!(
+                contract.execute_proposal(proposal_id),
+                Err(Error::InsufficientTreasuryBalance)
+            );
+            assert_eq!(contract.get_payout(proposal_id), None);
+            assert_eq!(contract.get_total_paid_out(), 0);
+        }
+
+        #[ink::test]
+        fn test_15_action_proposals() {
+            let mut contract = registered_contract();
+            let accounts = ink::env::test::default_accounts();
+
+            let action = ProposalAction {
+                callee: accounts.django,
+                selector: [0xde, 0xad, 0xbe, 0xef],
+                input: vec![1, 2, 3],
+                transferred_value: 0,
+            };
+
+            // Actions are only allowed on Governance and Technical proposals
+            let result = contract.create_proposal_with_payload(
+                String::from("Call From Treasury"),
+                String::from("Wrong proposal type"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                binary_voting_options(),
+                ProposalPayload::Calls(vec![action.clone()]),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            // An empty or oversized action list is rejected
+            let result = contract.create_proposal_with_payload(
+                String::from("No Actions"),
+                String::from("Nothing to call"),
+                ProposalType::Technical,
+                default_governance_params(),
+                binary_voting_options(),
+                ProposalPayload::Calls(vec![]),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            let result = contract.create_proposal_with_payload(
+                String::from("Too Many Actions"),
+                String::from("Over the limit"),
+                ProposalType::Technical,
+                default_governance_params(),
+                binary_voting_options(),
+                ProposalPayload::Calls(vec![action.clone(); MAX_PROPOSAL_ACTIONS + 1]),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            let proposal_id = contract.create_proposal_with_payload(
+                String::from("Configure Registry"),
+                String::from("Call the registry contract"),
+                ProposalType::Governance,
+                default_governance_params(),
+                binary_voting_options(),
+                ProposalPayload::Calls(vec![action.clone()]),
+            ).unwrap();
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.payload, ProposalPayload::Calls(vec![action]));
+            assert_eq!(contract.get_action_results(proposal_id), None);
+        }
+
+        #[ink::test]
+        fn test_16_membership_open() {
+            let accounts = ink::env::test::default_accounts();
+
+            // Anyone may propose and vote without registering
+            let mut contract = TreasuryGovernance::new();
+            assert_eq!(contract.get_membership_policy(), MembershipPolicy::Open);
+            let proposal_id = contract.create_proposal(
+                String::from("Open Vote"),
+                String::from("Anyone can vote"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+            ink::env::test::set_caller(accounts.bob);
+            assert!(contract.vote(proposal_id, 0).is_ok());
+        }
+
+        #[ink::test]
+        fn test_17_membership_registered_only() {
+            let accounts = ink::env::test::default_accounts();
+
+            // Voting and proposing require registration
+            let mut contract = TreasuryGovernance::with_membership_policy(MembershipPolicy::RegisteredOnly);
+            let result = contract.create_proposal(
+                String::from("Members Vote"),
+                String::from("Registered voters only"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            );
+            assert_eq!(result, Err(Error::NotRegistered));
+
+            contract.register_voter();
+            let proposal_id = contract.create_proposal(
+                String::from("Members Vote"),
+                String::from("Registered voters only"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::NotRegistered));
+            contract.register_voter();
+            assert!(contract.vote(proposal_id, 0).is_ok());
+        }
+
+        #[ink::test]
+        fn test_18_membership_owner_approved() {
+            let accounts = ink::env::test::default_accounts();
+
+            // Registration is an application until the owner approves it
+            let mut contract = TreasuryGovernance::with_membership_policy(MembershipPolicy::OwnerApproved);
+            contract.register_voter();
+            contract.approve_voter(account_id(accounts.alice)).unwrap();
+            let proposal_id = contract.create_proposal(
+                String::from("Approved Vote"),
+                String::from("Approved voters only"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.register_voter();
+            assert!(contract.is_pending_voter(account_id(accounts.bob)));
+            assert!(!contract.is_registered(account_id(accounts.bob)));
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::NotRegistered));
+
+            // Only the owner can approve
+            assert_eq!(contract.approve_voter(account_id(accounts.bob)), Err(Error::NotAuthorized));
+
+            // Only applicants can be approved
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(contract.approve_voter(account_id(accounts.charlie)), Err(Error::NotPendingVoter));
+            assert!(!contract.is_registered(account_id(accounts.charlie)));
+
+            contract.approve_voter(account_id(accounts.bob)).unwrap();
+            assert!(!contract.is_pending_voter(account_id(accounts.bob)));
+            assert_eq!(contract.get_total_voters(), 2);
+            assert_eq!(contract.approve_voter(account_id(accounts.bob)), Err(Error::NotPendingVoter));
+
+            ink::env::test::set_caller(accounts.bob);
+            assert!(contract.vote(proposal_id, 0).is_ok());
+        }
+
+        #[ink::test]
+        fn test_19_electorate_snapshot() {
+            let mut contract = TreasuryGovernance::new();
+            contract.total_voters = 10; // Simulate 10 registered voters
+
+            let proposal_id = contract.create_proposal(
+                String::from("Snapshot Test"),
+                String::from("Quorum uses the electorate at creation"),
+                ProposalType::Treasury,
+                default_governance_params(), // 10% quorum
+                default_voting_options(),
+            ).unwrap();
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.electorate_size, 10);
+            assert_eq!(proposal.total_voting_power, 10);
+
+            contract.vote(proposal_id, 0).unwrap();
+            assert!(contract.has_reached_quorum(proposal_id));
+
+            // Registering more voters afterwards does not move the goalposts
+            contract.total_voters = 100;
+            assert!(contract.has_reached_quorum(proposal_id));
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.voting_end + 1);
+            contract.update_proposal_status(proposal_id).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().status, ProposalStatus::Passed);
+        }
+
+        #[ink::test]
+        fn test_20_governance_token_configuration() {
+            let accounts = ink::env::test::default_accounts();
+
+            // Headcount voting by default
+            let contract = TreasuryGovernance::new();
+            assert_eq!(contract.get_governance_token(), None);
+
+            // Token-weighted voting is configured at construction; the checkpoint probe
+            // needs a deployed token, so it is covered by the end-to-end tests
+            let contract = TreasuryGovernance::init(
+                MembershipPolicy::RegisteredOnly,
+                Some(accounts.django),
+            );
+            assert_eq!(contract.get_governance_token(), Some(accounts.django));
+            assert_eq!(contract.get_membership_policy(), MembershipPolicy::RegisteredOnly);
+
+            // Padded account ids map back to the address they came from
+            assert_eq!(
+                TreasuryGovernance::account_address(account_id(accounts.django)),
+                accounts.django
+            );
+        }
+
+        #[ink::test]
+        fn test_21_vote_delegation() {
+            let accounts = ink::env::test::default_accounts();
+            let alice = account_id(accounts.alice);
+            let bob = account_id(accounts.bob);
+            let charlie = account_id(accounts.charlie);
+
+            let mut contract = TreasuryGovernance::new();
+
+            // Only registered voters can delegate or be delegated to
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.delegate(alice), Err(Error::NotRegistered));
+
+            for caller in [accounts.alice, accounts.bob, accounts.charlie] {
+                ink::env::test::set_caller(caller);
+                contract.register_voter();
+            }
+
+            // charlie -> bob -> alice
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.delegate(bob), Err(Error::DelegationCycle));
+            contract.delegate(alice).unwrap();
+            ink::env::test::set_caller(accounts.charlie);
+            contract.delegate(bob).unwrap();
+
+            assert_eq!(contract.get_delegation(charlie), Some(bob));
+            assert_eq!(contract.get_delegators(alice), vec![bob]);
+            assert_eq!(contract.resolve_delegate(charlie), alice);
+
+            // alice -> charlie would close the loop
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(contract.delegate(charlie), Err(Error::DelegationCycle));
+
+            let proposal_id = contract.create_proposal(
+                String::from("Delegated Vote"),
+                String::from("Alice votes for three"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            // Delegators cannot vote while delegating
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.vote(proposal_id, 1), Err(Error::VoteDelegated));
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.vote(proposal_id, 0).unwrap();
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.vote_counts[0], 3);
+            assert_eq!(proposal.total_voters, 3);
+            let vote = contract.get_user_vote(proposal_id, alice).unwrap();
+            assert_eq!(vote.weight, 3);
+            assert_eq!(vote.delegators, vec![bob, charlie]);
+
+            // Undelegating after the delegate voted does not allow a second vote
+            ink::env::test::set_caller(accounts.bob);
+            contract.undelegate().unwrap();
+            assert_eq!(contract.undelegate(), Err(Error::NotDelegated));
+            assert_eq!(contract.get_delegation(bob), None);
+            assert_eq!(contract.vote(proposal_id, 1), Err(Error::AlreadyVoted));
+        }
+
+        #[ink::test]
+        fn test_22_quadratic_voting() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = TreasuryGovernance::new();
+
+            let mut params = default_governance_params();
+            params.voting_method = VotingMethod::Quadratic { credits_per_voter: 100 };
+            let proposal_id = contract.create_proposal(
+                String::from("Funding Round"),
+                String::from("Split credits across grants"),
+                ProposalType::Treasury,
+                params,
+                default_voting_options(),
+            ).unwrap();
+
+            // Quadratic proposals need quadratic ballots and vice versa
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::InvalidVotingMethod));
+            let single_id = contract.create_proposal(
+                String::from("Plain Vote"),
+                String::from("Single choice"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+            assert_eq!(
+                contract.vote_quadratic(single_id, vec![(0, 4)]),
+                Err(Error::InvalidVotingMethod)
+            );
+
+            // Invalid allocations
+            assert_eq!(contract.vote_quadratic(proposal_id, vec![]), Err(Error::InvalidProposal));
+            assert_eq!(
+                contract.vote_quadratic(proposal_id, vec![(0, 4), (0, 9)]),
+                Err(Error::InvalidProposal)
+            );
+            assert_eq!(
+                contract.vote_quadratic(proposal_id, vec![(0, 64), (1, 37)]),
+                Err(Error::InsufficientCredits)
+            );
+
+            // Each option receives the square root of its credits
+            contract.vote_quadratic(proposal_id, vec![(0, 64), (1, 36)]).unwrap();
+            ink::env::test::set_caller(accounts.bob);
+            contract.vote_quadratic(proposal_id, vec![(1, 100)]).unwrap();
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.vote_counts, vec![8, 16, 0]);
+            assert_eq!(proposal.turnout, 2);
+            assert_eq!(proposal.total_voters, 2);
+
+            let vote = contract.get_user_vote(proposal_id, account_id(accounts.alice)).unwrap();
+            assert_eq!(vote.credits_spent, 100);
+            assert_eq!(vote.choices.len(), 2);
+            assert_eq!(vote.choices[0].weight, 8);
+            assert_eq!(vote.choices[1].weight, 6);
+
+            // The tally runs on the square-rooted counts
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.voting_end + 1);
+            contract.update_proposal_status(proposal_id).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().status, ProposalStatus::Passed);
+            let (winner, votes) = contract.get_winning_option(proposal_id).unwrap();
+            assert_eq!(winner, String::from("Reject"));
+            assert_eq!(votes, 16);
+        }
+
+        #[ink::test]
+        fn test_23_ranked_choice_voting() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = TreasuryGovernance::new();
+
+            let mut params = default_governance_params();
+            params.voting_method = VotingMethod::RankedChoice;
+            let proposal_id = contract.create_proposal(
+                String::from("Pick a Vendor"),
+                String::from("Rank the candidates"),
+                ProposalType::Other,
+                params,
+                default_voting_options(),
+            ).unwrap();
+
+            // Ranked proposals only take ranked ballots, and rankings must be valid
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::InvalidVotingMethod));
+            assert_eq!(contract.vote_ranked(proposal_id, vec![]), Err(Error::InvalidProposal));
+            assert_eq!(contract.vote_ranked(proposal_id, vec![0, 0]), Err(Error::InvalidProposal));
+            assert_eq!(contract.vote_ranked(proposal_id, vec![0, 3]), Err(Error::InvalidProposal));
+
+            // First preferences: 2 / 1 / 2, nobody has a majority
+            let ballots = [
+                (accounts.alice, vec![0, 1]),
+                (accounts.bob, vec![0, 1]),
+                (accounts.charlie, vec![1, 0]),
+                (accounts.django, vec![2, 1]),
+                (accounts.eve, vec![2]),
+            ];
+            for (caller, ranking) in ballots {
+                ink::env::test::set_caller(caller);
+                contract.vote_ranked(proposal_id, ranking).unwrap();
+            }
+
+            // Revoking moves the last ballot into the freed position, re-voting appends again
+            ink::env::test::set_caller(accounts.bob);
+            contract.revoke_vote(proposal_id).unwrap();
+            contract.vote_ranked(proposal_id, vec![0, 1]).unwrap();
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.vote_counts, vec![2, 1, 2]);
+
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.voting_end + 1);
+            contract.update_proposal_status(proposal_id).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().status, ProposalStatus::Passed);
+
+            // Option 1 is eliminated and its ballot transfers to option 0
+            let rounds = contract.get_runoff_rounds(proposal_id).unwrap();
+            assert_eq!(rounds.len(), 2);
+            assert_eq!(rounds[0].tallies, vec![2, 1, 2]);
+            assert_eq!(rounds[0].eliminated, Some(1));
+            assert_eq!(rounds[1].tallies, vec![3, 0, 2]);
+            assert_eq!(rounds[1].eliminated, None);
+
+            let (winner, votes) = contract.get_winning_option(proposal_id).unwrap();
+            assert_eq!(winner, String::from("Approve"));
+            assert_eq!(votes, 3);
+        }
+
+        #[ink::test]
+        fn test_24_approval_voting() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = TreasuryGovernance::new();
+
+            let grants = VotingOptions {
+                options: vec![
+                    String::from("Grant A"),
+                    String::from("Grant B"),
+                    String::from("Grant C"),
+                    String::from("Grant D"),
+                ],
+                roles: vec![],
+            };
+
+            // More winners than options is invalid
+            let mut params = default_governance_params();
+            params.voting_method = VotingMethod::Approval { winners: 5 };
+            let result = contract.create_proposal(
+                String::from("Grants"),
+                String::from("Too many winners"),
+                ProposalType::Treasury,
+                params.clone(),
+                grants.clone(),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            params.voting_method = VotingMethod::Approval { winners: 2 };
+            let proposal_id = contract.create_proposal(
+                String::from("Grants"),
+                String::from("Fund the two most approved grants"),
+                ProposalType::Treasury,
+                params,
+                grants,
+            ).unwrap();
+
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::InvalidVotingMethod));
+            assert_eq!(contract.vote_approval(proposal_id, vec![1, 1]), Err(Error::InvalidProposal));
+
+            let ballots = [
+                (accounts.alice, vec![0, 1]),
+                (accounts.bob, vec![1, 2]),
+                (accounts.charlie, vec![1, 3, 0]),
+            ];
+            for (caller, approved) in ballots {
+                ink::env::test::set_caller(caller);
+                contract.vote_approval(proposal_id, approved).unwrap();
+            }
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.vote_counts, vec![2, 3, 1, 1]);
+            assert_eq!(proposal.turnout, 3);
+            let vote = contract.get_user_vote(proposal_id, account_id(accounts.charlie)).unwrap();
+            assert_eq!(vote.choices.len(), 3);
+
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.voting_end + 1);
+            contract.update_proposal_status(proposal_id).unwrap();
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.status, ProposalStatus::Passed);
+            assert_eq!(proposal.winning_options, vec![1, 0]);
+            assert_eq!(
+                contract.get_winning_options(proposal_id).unwrap(),
+                vec![(String::from("Grant B"), 3), (String::from("Grant A"), 2)]
+            );
+
+            // A tie at the cut-off rejects
+            assert_eq!(TreasuryGovernance::top_options(&[3, 2, 2, 0], 2), None);
+            assert_eq!(TreasuryGovernance::top_options(&[3, 2, 1, 0], 2), Some(vec![0, 1]));
+            assert_eq!(TreasuryGovernance::top_options(&[3, 0, 0, 0], 2), None);
+        }
+
+        #[ink::test]
+        fn test_25_change_and_revoke_vote() {
+            let accounts = ink::env::test::default_accounts();
+            let alice = account_id(accounts.alice);
+            let mut contract = TreasuryGovernance::new();
+
+            for caller in [accounts.alice, accounts.bob] {
+                ink::env::test::set_caller(caller);
+                contract.register_voter();
             }
+            ink::env::test::set_caller(accounts.bob);
+            contract.delegate(alice).unwrap();
+
+            ink::env::test::set_caller(accounts.alice);
+            let proposal_id = contract.create_proposal(
+                String::from("Changeable Vote"),
+                String::from("Mistakes can be fixed"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            // Nothing to change or revoke before voting
+            assert_eq!(contract.change_vote(proposal_id, 1), Err(Error::NotVoted));
+            assert_eq!(contract.revoke_vote(proposal_id), Err(Error::NotVoted));
+
+            // The full weight, delegated weight included, moves with the vote
+            contract.vote(proposal_id, 0).unwrap();
+            assert_eq!(contract.change_vote(proposal_id, 5), Err(Error::InvalidProposal));
+            contract.change_vote(proposal_id, 1).unwrap();
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.vote_counts, vec![0, 2, 0]);
+            assert_eq!(contract.get_user_vote(proposal_id, alice).unwrap().choices[0].option_index, 1);
+
+            // Revoking removes the vote and releases the delegated weight
+            contract.revoke_vote(proposal_id).unwrap();
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.vote_counts, vec![0, 0, 0]);
+            assert_eq!(proposal.total_voters, 0);
+            assert_eq!(proposal.turnout, 0);
+            assert_eq!(contract.get_user_vote(proposal_id, alice), None);
+
+            // Voting again re-collects the delegation
+            contract.vote(proposal_id, 2).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().vote_counts, vec![0, 0, 2]);
+
+            // Votes are final once the voting period is over
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.voting_end + 1);
+            assert_eq!(contract.change_vote(proposal_id, 0), Err(Error::VotingPeriodEnded));
+            assert_eq!(contract.revoke_vote(proposal_id), Err(Error::VotingPeriodEnded));
         }
 
-        /// Get the winning option
-        #[ink(message)]
-        pub fn get_winning_option(&self, proposal_id: u32) -> Option<(String, u128)> {
-            if let Some(proposal) = self.proposals.get(proposal_id) {
-                let mut max_votes = 0u128;
-                let mut winning_index = 0usize;
+        #[ink::test]
+        fn test_26_commit_reveal_voting() {
+            let accounts = ink::env::test::default_accounts();
+            let alice = account_id(accounts.alice);
+            let mut contract = TreasuryGovernance::new();
 
-                for (i, &votes) in proposal.vote_counts.iter().enumerate() {
-                    if votes > max_votes {
-                        max_votes = votes;
-                        winning_index = i;
-                    }
-                }
+            let mut params = default_governance_params();
+            params.voting_method = VotingMethod::CommitReveal { reveal_period: VotingPeriod::ThreeDays };
+            let proposal_id = contract.create_proposal(
+                String::from("Secret Ballot"),
+                String::from("Sensitive treasury vote"),
+                ProposalType::Treasury,
+                params,
+                default_voting_options(),
+            ).unwrap();
 
-                if max_votes > 0 {
-                    Some((
-                        proposal.voting_options.options[winning_index].clone(),
-                        max_votes,
-                    ))
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.reveal_end, proposal.voting_end + VotingPeriod::ThreeDays.to_blocks());
+            assert_eq!(proposal.execution_time, proposal.reveal_end + ExecutionDelay::OneDay.to_blocks());
+
+            let salt = [7u8; 32];
+            let mut commitment = [0u8; 32];
+            ink::env::hash_encoded::<Keccak256, _>(&(proposal_id, alice, 0u32, salt), &mut commitment);
+
+            // Open votes are not accepted and commitments are one per voter
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::InvalidVotingMethod));
+            contract.commit_vote(proposal_id, commitment).unwrap();
+            assert_eq!(contract.commit_vote(proposal_id, commitment), Err(Error::AlreadyVoted));
+
+            // Bob commits but never reveals
+            ink::env::test::set_caller(accounts.bob);
+            contract.commit_vote(proposal_id, [1u8; 32]).unwrap();
+
+            // Nothing can be revealed while voting is open
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(contract.reveal_vote(proposal_id, 0, salt), Err(Error::NotInRevealPhase));
+
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.voting_end + 1);
+            contract.update_proposal_status(proposal_id).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().status, ProposalStatus::Revealing);
+            assert_eq!(contract.commit_vote(proposal_id, commitment), Err(Error::ProposalNotActive));
+
+            // The reveal must match the commitment
+            assert_eq!(contract.reveal_vote(proposal_id, 1, salt), Err(Error::InvalidReveal));
+            assert_eq!(contract.reveal_vote(proposal_id, 0, [0u8; 32]), Err(Error::InvalidReveal));
+            contract.reveal_vote(proposal_id, 0, salt).unwrap();
+            assert_eq!(contract.reveal_vote(proposal_id, 0, salt), Err(Error::NotVoted));
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.vote_counts, vec![1, 0, 0]);
+            assert_eq!(proposal.total_voters, 1);
+
+            // Unrevealed commitments are ignored in the tally
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.reveal_end + 1);
+            assert_eq!(contract.reveal_vote(proposal_id, 0, salt), Err(Error::NotInRevealPhase));
+            contract.update_proposal_status(proposal_id).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().status, ProposalStatus::Passed);
         }
 
-        /// Get contract statistics
-        #[ink(message)]
-        pub fn get_stats(&self) -> (u32, u32, u32) {
-            let total = self.proposal_ids.len() as u32;
-            let mut active = 0u32;
-            let mut executed = 0u32;
+        #[ink::test]
+        fn test_27_signed_votes() {
+            let accounts = ink::env::test::default_accounts();
+            let bob = account_id(accounts.bob);
+            let mut contract = TreasuryGovernance::new();
 
-            for &id in &self.proposal_ids {
-                if let Some(proposal) = self.proposals.get(id) {
-                    match proposal.status {
-                        ProposalStatus::Active => active = active.saturating_add(1),
-                        ProposalStatus::Executed => executed = executed.saturating_add(1),
-                        _ => {}
-                    }
-                }
-            }
+            // An off-chain key and the Ethereum-style address derived from it
+            let secp = secp256k1::Secp256k1::new();
+            let secret_key = secp256k1::SecretKey::from_slice(&[0x11; 32]).unwrap();
+            let public_key = secret_key.public_key(&secp).serialize_uncompressed();
+            let mut public_key_hash = [0u8; 32];
+            ink::env::hash_bytes::<Keccak256>(&public_key[1..], &mut public_key_hash);
+            let signer = H160::from_slice(&public_key_hash[12..]);
+            let sign = |digest: [u8; 32]| {
+                let (recovery_id, compact) = secp
+                    .sign_ecdsa_recoverable(&secp256k1::Message::from_digest(digest), &secret_key)
+                    .serialize_compact();
+                let mut signature = [0u8; 65];
+                signature[..64].copy_from_slice(&compact);
+                signature[64] = recovery_id.to_i32() as u8;
+                signature
+            };
 
-            (total, active, executed)
+            let proposal_id = contract.create_proposal(
+                String::from("Gasless Vote"),
+                String::from("Relayed ballots"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            assert_eq!(contract.get_vote_nonce(bob), 0);
+
+            // Digests are bound to the proposal, option and nonce
+            let digest = contract.signed_vote_digest(proposal_id, 0, 0);
+            assert_eq!(digest, contract.signed_vote_digest(proposal_id, 0, 0));
+            assert_ne!(digest, contract.signed_vote_digest(proposal_id, 1, 0));
+            assert_ne!(digest, contract.signed_vote_digest(proposal_id, 0, 1));
+
+            let ballot = SignedBallot {
+                voter: accounts.bob,
+                proposal_id,
+                option_index: 0,
+                nonce: 1,
+                signature: [0u8; 65],
+            };
+
+            // Out-of-order nonces are rejected before the signature is checked
+            assert_eq!(
+                contract.submit_signed_votes(vec![ballot.clone()]),
+                Err(Error::InvalidNonce)
+            );
+
+            // A signature that does not recover to the voter is rejected
+            let ballot = SignedBallot { nonce: 0, ..ballot };
+            assert_eq!(
+                contract.submit_signed_votes(vec![ballot]),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(contract.get_user_vote(proposal_id, bob), None);
+
+            // An empty batch is a no-op
+            assert_eq!(contract.submit_signed_votes(vec![]), Ok(()));
+
+            // A valid signature over the digest is tallied for the signer and consumes the nonce
+            let ballot = SignedBallot {
+                voter: signer,
+                proposal_id,
+                option_index: 0,
+                nonce: 0,
+                signature: sign(contract.signed_vote_digest(proposal_id, 0, 0)),
+            };
+            contract.submit_signed_votes(vec![ballot.clone()]).unwrap();
+
+            let signer_account = account_id(signer);
+            assert_eq!(contract.get_vote_nonce(signer_account), 1);
+            assert_eq!(contract.get_user_vote(proposal_id, signer_account).unwrap().weight, 1);
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.vote_counts[0], 1);
+            assert_eq!(proposal.total_voters, 1);
+
+            // Replaying the same ballot fails on its spent nonce
+            assert_eq!(contract.submit_signed_votes(vec![ballot]), Err(Error::InvalidNonce));
         }
-    }
 
-    // ========== TESTS ==========
+        #[ink::test]
+        fn test_28_cancel_proposal() {
+            let accounts = ink::env::test::default_accounts();
+            let charlie = account_id(accounts.charlie);
+            let mut contract = TreasuryGovernance::new();
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+            // Bob proposes and withdraws before anyone votes
+            ink::env::test::set_caller(accounts.bob);
+            let withdrawn = contract.create_proposal(
+                String::from("Withdrawn"),
+                String::from("Pulled by the proposer"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
 
-        /// Helper function to create default governance parameters
-        fn default_governance_params() -> GovernanceParameters {
-            GovernanceParameters {
-                voting_period: VotingPeriod::ThreeDays,
-                quorum_threshold: QuorumThreshold::Ten,
-                execution_delay: ExecutionDelay::OneDay,
-            }
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(contract.cancel_proposal(withdrawn), Err(Error::NotAuthorized));
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.cancel_proposal(withdrawn).unwrap();
+            assert_eq!(contract.get_proposal(withdrawn).unwrap().status, ProposalStatus::Cancelled);
+            assert_eq!(contract.cancel_proposal(withdrawn), Err(Error::ProposalNotCancellable));
+            assert_eq!(contract.vote(withdrawn, 0), Err(Error::ProposalNotActive));
+
+            // Once voting has started only the owner or guardian can cancel
+            let contested = contract.create_proposal(
+                String::from("Contested"),
+                String::from("Already has votes"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.vote(contested, 0).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.cancel_proposal(contested), Err(Error::ProposalHasVotes));
+            assert_eq!(contract.set_guardian(Some(charlie)), Err(Error::NotAuthorized));
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_guardian(Some(charlie)).unwrap();
+            assert_eq!(contract.get_guardian(), Some(charlie));
+
+            ink::env::test::set_caller(accounts.charlie);
+            contract.cancel_proposal(contested).unwrap();
+            assert_eq!(contract.get_proposal(contested).unwrap().status, ProposalStatus::Cancelled);
+
+            // Cancelled proposals are not counted as active
+            assert_eq!(contract.get_stats(), (2, 0, 0));
         }
 
-        /// Helper function to create default voting options
-        fn default_voting_options() -> VotingOptions {
-            VotingOptions {
-                options: vec![
-                    String::from("Approve"),
-                    String::from("Reject"),
-                    String::from("Abstain"),
-                ],
-            }
+        #[ink::test]
+        fn test_29_execution_window() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = TreasuryGovernance::new();
+            assert_eq!(contract.get_execution_grace_period(), DEFAULT_EXECUTION_GRACE_PERIOD);
+
+            // Only the owner configures the grace period
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.set_execution_grace_period(100), Err(Error::NotAuthorized));
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_execution_grace_period(100).unwrap();
+            contract.set_guardian(Some(account_id(accounts.django))).unwrap();
+
+            let proposal_id = contract.create_proposal(
+                String::from("Stale Signal"),
+                String::from("Never executed"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+            contract.vote(proposal_id, 0).unwrap();
+
+            // Later changes to the grace period leave existing proposals alone
+            contract.set_execution_grace_period(0).unwrap();
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.execution_grace_period, 100);
+            assert_eq!(proposal.execution_deadline(), proposal.execution_time + 100);
+
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.voting_end + 1);
+            contract.update_proposal_status(proposal_id).unwrap();
+
+            // Cannot expire while the execution window is still open
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.execution_time + 100);
+            assert_eq!(contract.expire_proposal(proposal_id), Err(Error::ProposalNotExpired));
+
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.execution_time + 101);
+            assert_eq!(contract.execute_proposal(proposal_id), Err(Error::ProposalExpired));
+
+            // Nor can the owner or guardian veto it once the window has closed
+            assert_eq!(contract.cancel_proposal(proposal_id), Err(Error::ProposalExpired));
+            ink::env::test::set_caller(accounts.django);
+            assert_eq!(contract.cancel_proposal(proposal_id), Err(Error::ProposalExpired));
+
+            // Anyone can record the expiry
+            ink::env::test::set_caller(accounts.charlie);
+            contract.expire_proposal(proposal_id).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().status, ProposalStatus::Expired);
+            assert_eq!(contract.expire_proposal(proposal_id), Err(Error::ProposalNotExpired));
+            assert_eq!(contract.execute_proposal(proposal_id), Err(Error::ProposalNotReadyForExecution));
+            assert_eq!(contract.cancel_proposal(proposal_id), Err(Error::ProposalNotCancellable));
         }
 
         #[ink::test]
-        fn test_1_new_contract_initialization() {
-            let contract = TreasuryGovernance::new();
-            assert_eq!(contract.get_total_voters(), 0);
-            assert_eq!(contract.get_all_proposal_ids().len(), 0);
-            let (total, active, executed) = contract.get_stats();
-            assert_eq!(total, 0);
-            assert_eq!(active, 0);
-            assert_eq!(executed, 0);
+        fn test_30_automatic_finalization() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = TreasuryGovernance::new();
+
+            let passing = contract.create_proposal(
+                String::from("Passing"),
+                String::from("Executed without a status update"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+            let tied = contract.create_proposal(
+                String::from("Tied"),
+                String::from("Split vote"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+
+            contract.vote(passing, 0).unwrap();
+            contract.vote(tied, 0).unwrap();
+            ink::env::test::set_caller(accounts.bob);
+            contract.vote(tied, 1).unwrap();
+
+            // Queries report the outcome as soon as voting closes
+            let proposal = contract.get_proposal(passing).unwrap();
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.voting_end + 1);
+            let proposal = contract.get_proposal(passing).unwrap();
+            assert_eq!(proposal.status, ProposalStatus::Passed);
+            assert_eq!(proposal.winning_options, vec![0]);
+            assert_eq!(contract.get_proposal(tied).unwrap().status, ProposalStatus::Rejected);
+            assert_eq!(contract.get_stats(), (2, 0, 0));
+
+            // Execution finalizes the tally inline
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.execution_time);
+            contract.execute_proposal(passing).unwrap();
+            assert_eq!(contract.get_proposal(passing).unwrap().status, ProposalStatus::Executed);
+            assert_eq!(contract.execute_proposal(tied), Err(Error::ProposalNotReadyForExecution));
+
+            // Persisting an already-visible outcome changes nothing
+            contract.update_proposal_status(tied).unwrap();
+            assert_eq!(contract.get_proposal(tied).unwrap().status, ProposalStatus::Rejected);
+            assert_eq!(contract.get_stats(), (2, 0, 1));
         }
 
         #[ink::test]
-        fn test_2_voter_registration() {
+        fn test_31_rejection_reasons() {
+            let accounts = ink::env::test::default_accounts();
             let mut contract = TreasuryGovernance::new();
-            contract.register_voter();
-            assert_eq!(contract.get_total_voters(), 1);
-            // Registering again should not increase count
-            contract.register_voter();
-            assert_eq!(contract.get_total_voters(), 1);
+            contract.set_execution_grace_period(10).unwrap();
+
+            let mut ids = Vec::new();
+            for title in ["Withdrawn", "Vetoed", "Tied", "Executed", "Expired"] {
+                ids.push(contract.create_proposal(
+                    String::from(title),
+                    String::from("Lifecycle"),
+                    ProposalType::Other,
+                    default_governance_params(),
+                    default_voting_options(),
+                ).unwrap());
+            }
+            let (withdrawn, vetoed, tied, executed, expired) = (ids[0], ids[1], ids[2], ids[3], ids[4]);
+            assert_eq!(contract.get_proposal(withdrawn).unwrap().rejection_reason, None);
+
+            contract.cancel_proposal(withdrawn).unwrap();
+            for id in [vetoed, tied, executed, expired] {
+                contract.vote(id, 0).unwrap();
+            }
+            ink::env::test::set_caller(accounts.bob);
+            contract.vote(tied, 1).unwrap();
+
+            let voting_end = contract.get_proposal(vetoed).unwrap().voting_end;
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(voting_end + 1);
+            contract.update_proposal_status(tied).unwrap();
+
+            // The owner stopping a passed proposal is a veto
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(contract.get_proposal(vetoed).unwrap().status, ProposalStatus::Passed);
+            contract.cancel_proposal(vetoed).unwrap();
+
+            let reason = |id| contract.get_proposal(id).unwrap().rejection_reason;
+            assert_eq!(reason(withdrawn), Some(RejectionReason::Cancelled));
+            assert_eq!(reason(vetoed), Some(RejectionReason::Vetoed));
+            assert_eq!(reason(tied), Some(RejectionReason::Tie));
+
+            let execution_time = contract.get_proposal(executed).unwrap().execution_time;
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(execution_time);
+            contract.execute_proposal(executed).unwrap();
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(execution_time + 11);
+            contract.expire_proposal(expired).unwrap();
+
+            // Every transition emits `ProposalStatusChanged`, finalizing first where it was still pending
+            use ProposalStatus::{Active, Cancelled, Executed, Expired, Passed, Rejected};
+            assert_eq!(status_changes(withdrawn), vec![(Active, Cancelled, Some(RejectionReason::Cancelled))]);
+            assert_eq!(status_changes(tied), vec![(Active, Rejected, Some(RejectionReason::Tie))]);
+            assert_eq!(
+                status_changes(vetoed),
+                vec![(Active, Passed, None), (Passed, Cancelled, Some(RejectionReason::Vetoed))]
+            );
+            assert_eq!(status_changes(executed), vec![(Active, Passed, None), (Passed, Executed, None)]);
+            assert_eq!(status_changes(expired), vec![(Active, Passed, None), (Passed, Expired, None)]);
         }
 
         #[ink::test]
-        fn test_3_create_proposal_success() {
+        fn test_32_approval_thresholds() {
+            let accounts = ink::env::test::default_accounts();
             let mut contract = TreasuryGovernance::new();
+
+            assert!(ApprovalThreshold::SimpleMajority.is_met(51, 100));
+            assert!(!ApprovalThreshold::SimpleMajority.is_met(50, 100));
+            assert!(ApprovalThreshold::SixtyPercent.is_met(60, 100));
+            assert!(!ApprovalThreshold::TwoThirds.is_met(66, 100));
+            assert!(ApprovalThreshold::Unanimous.is_met(100, 100));
+            assert!(!ApprovalThreshold::Unanimous.is_met(0, 0));
+
+            // Roles must match the options one to one
+            let mut options = default_voting_options();
+            options.roles = vec![OptionRole::Choice, OptionRole::Abstain];
             let result = contract.create_proposal(
-                String::from("Treasury Fund Allocation"),
-                String::from("Allocate 1000 tokens for development"),
-                ProposalType::Treasury,
+                String::from("Bad Roles"),
+                String::from("Too few roles"),
+                ProposalType::Other,
+                default_governance_params(),
+                options.clone(),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            options.roles = vec![OptionRole::Choice, OptionRole::Choice, OptionRole::Abstain];
+
+            // Ranked and approval tallies cannot honour abstentions
+            for voting_method in [VotingMethod::RankedChoice, VotingMethod::Approval { winners: 1 }] {
+                let mut params = default_governance_params();
+                params.voting_method = voting_method;
+                let result = contract.create_proposal(
+                    String::from("Bad Roles"),
+                    String::from("Abstain on a ranked or approval ballot"),
+                    ProposalType::Other,
+                    params,
+                    options.clone(),
+                );
+                assert_eq!(result, Err(Error::InvalidProposal));
+            }
+
+            let majority = contract.create_proposal(
+                String::from("Majority"),
+                String::from("Abstentions do not count against"),
+                ProposalType::Other,
                 default_governance_params(),
+                options,
+            ).unwrap();
+
+            let mut params = default_governance_params();
+            params.approval_threshold = ApprovalThreshold::TwoThirds;
+            let supermajority = contract.create_proposal(
+                String::from("Supermajority"),
+                String::from("Plurality is not enough"),
+                ProposalType::Other,
+                params,
                 default_voting_options(),
+            ).unwrap();
+
+            let ballots = [
+                (accounts.alice, 0),
+                (accounts.bob, 0),
+                (accounts.charlie, 1),
+                (accounts.django, 2),
+                (accounts.eve, 2),
+            ];
+            for (caller, option) in ballots {
+                ink::env::test::set_caller(caller);
+                contract.vote(majority, option).unwrap();
+                contract.vote(supermajority, option).unwrap();
+            }
+
+            let voting_end = contract.get_proposal(majority).unwrap().voting_end;
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(voting_end + 1);
+
+            // 2 of 3 non-abstaining votes is a majority
+            let proposal = contract.get_proposal(majority).unwrap();
+            assert_eq!(proposal.status, ProposalStatus::Passed);
+            assert_eq!(proposal.winning_options, vec![0]);
+
+            // 2 of 5 wins the plurality but misses two thirds
+            let proposal = contract.get_proposal(supermajority).unwrap();
+            assert_eq!(proposal.status, ProposalStatus::Rejected);
+            assert_eq!(proposal.rejection_reason, Some(RejectionReason::BelowThreshold));
+        }
+
+        #[ink::test]
+        fn test_33_binary_proposals() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = TreasuryGovernance::new();
+
+            // Binary proposals need exactly one for and one against option
+            let mut options = binary_voting_options();
+            options.roles = vec![OptionRole::For, OptionRole::For, OptionRole::Against];
+            let result = contract.create_proposal(
+                String::from("Two Fors"),
+                String::from("Ambiguous"),
+                ProposalType::Other,
+                default_governance_params(),
+                options,
             );
-            assert!(result.is_ok());
-            let proposal_id = result.unwrap();
-            assert_eq!(proposal_id, 1);
+            assert_eq!(result, Err(Error::InvalidProposal));
 
+            let mut params = default_governance_params();
+            params.voting_method = VotingMethod::RankedChoice;
+            let result = contract.create_proposal(
+                String::from("Ranked Binary"),
+                String::from("Nothing to rank"),
+                ProposalType::Other,
+                params,
+                binary_voting_options(),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            let mut ids = Vec::new();
+            for title in ["Defeated", "Carried"] {
+                ids.push(contract.create_proposal(
+                    String::from(title),
+                    String::from("Yes or no"),
+                    ProposalType::Other,
+                    default_governance_params(),
+                    binary_voting_options(),
+                ).unwrap());
+            }
+            let (defeated, carried) = (ids[0], ids[1]);
+
+            // Against outvotes for; abstentions only count toward quorum
+            let ballots = [
+                (accounts.alice, 0, 0),
+                (accounts.bob, 1, 2),
+                (accounts.charlie, 1, 2),
+                (accounts.django, 2, 2),
+            ];
+            for (caller, on_defeated, on_carried) in ballots {
+                ink::env::test::set_caller(caller);
+                contract.vote(defeated, on_defeated).unwrap();
+                contract.vote(carried, on_carried).unwrap();
+            }
+
+            let voting_end = contract.get_proposal(defeated).unwrap().voting_end;
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(voting_end + 1);
+
+            let proposal = contract.get_proposal(defeated).unwrap();
+            assert_eq!(proposal.status, ProposalStatus::Rejected);
+            assert_eq!(proposal.rejection_reason, Some(RejectionReason::BelowThreshold));
+
+            // A lone for vote carries against three abstentions
+            let proposal = contract.get_proposal(carried).unwrap();
+            assert_eq!(proposal.turnout, 4);
+            assert_eq!(proposal.status, ProposalStatus::Passed);
+            assert_eq!(proposal.winning_options, vec![0]);
+        }
+
+        #[ink::test]
+        fn test_34_custom_durations() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = TreasuryGovernance::new();
+            assert_eq!(VotingPeriod::Custom(14_400).to_blocks(), 14_400);
+            assert_eq!(ExecutionDelay::Custom(5).to_blocks(), 5);
+
+            // A 48-hour emergency vote on a chain with 12 second blocks
+            let mut params = default_governance_params();
+            params.voting_period = VotingPeriod::Custom(48 * 60 * 5);
+            params.execution_delay = ExecutionDelay::Custom(0);
+            let proposal_id = contract.create_proposal(
+                String::from("Emergency"),
+                String::from("Short vote"),
+                ProposalType::Other,
+                params.clone(),
+                default_voting_options(),
+            ).unwrap();
             let proposal = contract.get_proposal(proposal_id).unwrap();
-            assert_eq!(proposal.title, String::from("Treasury Fund Allocation"));
-            assert_eq!(proposal.status, ProposalStatus::Active);
-            assert_eq!(proposal.vote_counts.len(), 3);
+            assert_eq!(proposal.voting_end, proposal.created_at + 14_400);
+            assert_eq!(proposal.execution_time, proposal.voting_end);
+
+            // Durations outside the bounds are rejected
+            params.voting_period = VotingPeriod::Custom(10);
+            let result = contract.create_proposal(
+                String::from("Too Short"),
+                String::from("Ten blocks"),
+                ProposalType::Other,
+                params.clone(),
+                default_voting_options(),
+            );
+            assert_eq!(result, Err(Error::InvalidDuration));
+
+            // Only the owner sets the bounds, and they must be consistent
+            let bounds = DurationBounds {
+                min_voting_period: 10,
+                max_voting_period: 100,
+                min_execution_delay: 0,
+                max_execution_delay: 100,
+            };
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.set_duration_bounds(bounds.clone()), Err(Error::NotAuthorized));
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(
+                contract.set_duration_bounds(DurationBounds { min_voting_period: 200, ..bounds.clone() }),
+                Err(Error::InvalidDuration)
+            );
+            contract.set_duration_bounds(bounds.clone()).unwrap();
+            assert_eq!(contract.get_duration_bounds(), bounds);
+
+            assert!(contract.create_proposal(
+                String::from("Now Allowed"),
+                String::from("Ten blocks"),
+                ProposalType::Other,
+                params,
+                default_voting_options(),
+            ).is_ok());
+            let result = contract.create_proposal(
+                String::from("Too Long"),
+                String::from("Preset beyond the new maximum"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            );
+            assert_eq!(result, Err(Error::InvalidDuration));
         }
 
         #[ink::test]
-        fn test_4_create_proposal_validation() {
+        fn test_35_basis_point_quorum() {
+            let accounts = ink::env::test::default_accounts();
             let mut contract = TreasuryGovernance::new();
+            contract.total_voters = 9; // Simulate 9 registered voters
+
+            assert_eq!(QuorumThreshold::Ten.to_basis_points(), 1_000);
+            assert_eq!(QuorumThreshold::Custom(1_250).to_percentage(), 12);
+
+            // 10% of 9 voters rounds up to one vote instead of down to zero
+            let proposal_id = contract.create_proposal(
+                String::from("Rounded Quorum"),
+                String::from("Ceiling rounding"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().required_quorum_votes(), 1);
+            assert!(!contract.has_reached_quorum(proposal_id));
+
+            // Custom quorums must stay within the owner's limits
+            let mut params = default_governance_params();
+            params.quorum_threshold = QuorumThreshold::Custom(50);
+            let result = contract.create_proposal(
+                String::from("Tiny Quorum"),
+                String::from("Half a percent"),
+                ProposalType::Other,
+                params.clone(),
+                default_voting_options(),
+            );
+            assert_eq!(result, Err(Error::InvalidQuorum));
+
+            let limits = QuorumLimits {
+                min_basis_points: 10,
+                max_basis_points: 5_000,
+                min_votes: 3,
+            };
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.set_quorum_limits(limits.clone()), Err(Error::NotAuthorized));
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(
+                contract.set_quorum_limits(QuorumLimits { max_basis_points: 10_001, ..limits.clone() }),
+                Err(Error::InvalidQuorum)
+            );
+            contract.set_quorum_limits(limits.clone()).unwrap();
+            assert_eq!(contract.get_quorum_limits(), limits);
+
+            // The absolute floor applies on top of the percentage
+            let proposal_id = contract.create_proposal(
+                String::from("Tiny Quorum"),
+                String::from("Half a percent"),
+                ProposalType::Other,
+                params,
+                default_voting_options(),
+            ).unwrap();
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            assert_eq!(proposal.min_quorum_votes, 3);
+            assert_eq!(proposal.required_quorum_votes(), 3);
+        }
+
+        #[ink::test]
+        fn test_36_type_policies() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = registered_contract();
+            assert_eq!(contract.get_type_policy(ProposalType::Treasury), TypePolicy::default());
+
+            let policy = TypePolicy {
+                min_voting_period: VotingPeriod::SevenDays.to_blocks(),
+                min_quorum_basis_points: 2_000,
+                min_execution_delay: ExecutionDelay::TwoDays.to_blocks(),
+                allowed_proposers: vec![Role::Admin, Role::Member],
+            };
+            let change = ProposalPayload::Configure(ConfigChange::SetTypePolicy(
+                ProposalType::Treasury,
+                policy.clone(),
+            ));
+
+            // Configuration changes need a Governance proposal
+            let result = contract.create_proposal_with_payload(
+                String::from("Misfiled Policy"),
+                String::from("Configure from a treasury proposal"),
+                ProposalType::Treasury,
+                default_governance_params(),
+                binary_voting_options(),
+                change.clone(),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
+
+            let proposal_id = contract.create_proposal_with_payload(
+                String::from("Stricter Treasury"),
+                String::from("Raise the bar for spends"),
+                ProposalType::Governance,
+                default_governance_params(),
+                binary_voting_options(),
+                change,
+            ).unwrap();
+            contract.vote(proposal_id, 0).unwrap();
 
-            // Test empty options
-            let empty_options = VotingOptions { options: vec![] };
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.execution_time);
+            contract.execute_proposal(proposal_id).unwrap();
+            assert_eq!(contract.get_type_policy(ProposalType::Treasury), policy);
+
+            // Treasury proposals must now meet the minimums
             let result = contract.create_proposal(
-                String::from("Invalid Proposal"),
-                String::from("Should fail"),
+                String::from("Quick Spend"),
+                String::from("Too fast"),
                 ProposalType::Treasury,
                 default_governance_params(),
-                empty_options,
+                default_voting_options(),
             );
-            assert_eq!(result, Err(Error::InvalidProposal));
+            assert_eq!(result, Err(Error::InvalidDuration));
 
-            // Test too many options (>10)
-            let too_many_options = VotingOptions {
-                options: (1..=11).map(|i| format!("Option {}", i)).collect(),
+            let params = GovernanceParameters {
+                voting_period: VotingPeriod::SevenDays,
+                quorum_threshold: QuorumThreshold::Twenty,
+                execution_delay: ExecutionDelay::TwoDays,
+                ..default_governance_params()
             };
             let result = contract.create_proposal(
-                String::from("Invalid Proposal"),
-                String::from("Should fail"),
+                String::from("Low Quorum"),
+                String::from("Below the policy quorum"),
                 ProposalType::Treasury,
-                default_governance_params(),
-                too_many_options,
+                GovernanceParameters { quorum_threshold: QuorumThreshold::Ten, ..params.clone() },
+                default_voting_options(),
             );
-            assert_eq!(result, Err(Error::InvalidProposal));
-        }
+            assert_eq!(result, Err(Error::InvalidQuorum));
 
-        #[ink::test]
-        fn test_5_vote_success_and_tracking() {
-            let mut contract = TreasuryGovernance::new();
-            let proposal_id = contract.create_proposal(
-                String::from("Test Proposal"),
-                String::from("Test"),
+            // Proposers outside the listed roles may not propose treasury spends
+            contract.grant_role(Role::Proposer, account_id(accounts.bob)).unwrap();
+            ink::env::test::set_caller(accounts.bob);
+            let result = contract.create_proposal(
+                String::from("Outsider"),
+                String::from("Not a member"),
                 ProposalType::Treasury,
-                default_governance_params(),
+                params.clone(),
                 default_voting_options(),
-            ).unwrap();
+            );
+            assert_eq!(result, Err(Error::NotAuthorized));
 
-            // Vote on the proposal
-            let result = contract.vote(proposal_id, 0);
-            assert!(result.is_ok());
+            contract.register_voter();
+            assert!(contract.create_proposal(
+                String::from("Member Spend"),
+                String::from("Meets the policy"),
+                ProposalType::Treasury,
+                params,
+                default_voting_options(),
+            ).is_ok());
 
-            // Verify vote was recorded
-            let proposal = contract.get_proposal(proposal_id).unwrap();
-            assert_eq!(proposal.vote_counts[0], 1);
-            assert_eq!(proposal.total_voters, 1);
+            // Other proposal types are unaffected
+            assert!(contract.create_proposal(
+                String::from("Signal"),
+                String::from("Default policy"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            ).is_ok());
         }
 
         #[ink::test]
-        fn test_6_vote_error_cases() {
-            let mut contract = TreasuryGovernance::new();
-            let proposal_id = contract.create_proposal(
-                String::from("Test Proposal"),
-                String::from("Test"),
-                ProposalType::Treasury,
+        fn test_37_governance_configuration() {
+            let accounts = ink::env::test::default_accounts();
+            let bob = account_id(accounts.bob);
+            let charlie = account_id(accounts.charlie);
+            let mut contract = registered_contract();
+            assert_eq!(contract.get_owner(), account_id(accounts.alice));
+
+            // Invalid limits are rejected when proposed
+            let result = contract.create_proposal_with_payload(
+                String::from("Broken Limits"),
+                String::from("Minimum above maximum"),
+                ProposalType::Governance,
                 default_governance_params(),
-                default_voting_options(),
-            ).unwrap();
+                binary_voting_options(),
+                ProposalPayload::Configure(ConfigChange::SetQuorumLimits(QuorumLimits {
+                    min_basis_points: 2_000,
+                    max_basis_points: 1_000,
+                    min_votes: 0,
+                })),
+            );
+            assert_eq!(result, Err(Error::InvalidQuorum));
+
+            let mut new_defaults = default_governance_params();
+            new_defaults.quorum_threshold = QuorumThreshold::Twenty;
+            let changes = [
+                ConfigChange::SetOwner(bob),
+                ConfigChange::SetGuardian(Some(charlie)),
+                ConfigChange::SetMembershipPolicy(MembershipPolicy::OwnerApproved),
+                ConfigChange::SetDefaultParameters(new_defaults.clone()),
+                ConfigChange::SetExecutionGracePeriod(500),
+            ];
+            let mut ids = Vec::new();
+            for change in changes {
+                let proposal_id = contract.create_proposal_with_payload(
+                    String::from("Reconfigure"),
+                    String::from("Evolve the rules"),
+                    ProposalType::Governance,
+                    default_governance_params(),
+                    binary_voting_options(),
+                    ProposalPayload::Configure(change),
+                ).unwrap();
+                contract.vote(proposal_id, 0).unwrap();
+                ids.push(proposal_id);
+            }
 
-            // Test double voting
-            contract.vote(proposal_id, 0).unwrap();
-            assert_eq!(contract.vote(proposal_id, 1), Err(Error::AlreadyVoted));
+            let execution_time = contract.get_proposal(ids[0]).unwrap().execution_time;
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(execution_time);
+            for proposal_id in ids {
+                contract.execute_proposal(proposal_id).unwrap();
+            }
 
-            // Test non-existent proposal
-            assert_eq!(contract.vote(999, 0), Err(Error::ProposalNotFound));
+            assert_eq!(contract.get_owner(), bob);
+            assert_eq!(contract.get_guardian(), Some(charlie));
+            assert_eq!(contract.get_membership_policy(), MembershipPolicy::OwnerApproved);
+            assert_eq!(contract.get_default_params(), new_defaults);
+            assert_eq!(contract.get_execution_grace_period(), 500);
 
-            // Test invalid option index
-            let proposal_id2 = contract.create_proposal(
-                String::from("Test 2"),
-                String::from("Test"),
-                ProposalType::Treasury,
-                default_governance_params(),
+            // The new owner holds the owner-only messages
+            assert_eq!(contract.set_guardian(None), Err(Error::NotAuthorized));
+            ink::env::test::set_caller(accounts.bob);
+            contract.set_guardian(None).unwrap();
+
+            // Proposals can be created with the governance-set defaults
+            contract.register_voter();
+            contract.approve_voter(bob).unwrap();
+            let proposal_id = contract.create_proposal_with_defaults(
+                String::from("Default Rules"),
+                String::from("Uses the stored parameters"),
+                ProposalType::Other,
                 default_voting_options(),
             ).unwrap();
-            assert_eq!(contract.vote(proposal_id2, 10), Err(Error::InvalidProposal));
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().governance_params, new_defaults);
         }
 
         #[ink::test]
-        fn test_7_governance_parameter_enums() {
-            // Test voting period conversions (6 second block time)
-            assert_eq!(VotingPeriod::ThreeDays.to_blocks(), 43_200);
-            assert_eq!(VotingPeriod::SevenDays.to_blocks(), 100_800);
-            assert_eq!(VotingPeriod::FourteenDays.to_blocks(), 201_600);
-            assert_eq!(VotingPeriod::ThirtyDays.to_blocks(), 432_000);
+        fn test_38_code_upgrades() {
+            let mut contract = registered_contract();
+            let code_hash = H256::from([7u8; 32]);
+
+            // Upgrades are Technical proposals only
+            let result = contract.create_proposal_with_payload(
+                String::from("Misfiled Upgrade"),
+                String::from("Upgrade from a governance proposal"),
+                ProposalType::Governance,
+                default_governance_params(),
+                binary_voting_options(),
+                ProposalPayload::Upgrade(code_hash),
+            );
+            assert_eq!(result, Err(Error::InvalidProposal));
 
-            // Test quorum threshold percentages
-            assert_eq!(QuorumThreshold::Five.to_percentage(), 5);
-            assert_eq!(QuorumThreshold::Ten.to_percentage(), 10);
-            assert_eq!(QuorumThreshold::Twenty.to_percentage(), 20);
-            assert_eq!(QuorumThreshold::TwentyFive.to_percentage(), 25);
+            let proposal_id = contract.create_proposal_with_payload(
+                String::from("Upgrade"),
+                String::from("Ship the bug fix"),
+                ProposalType::Technical,
+                default_governance_params(),
+                binary_voting_options(),
+                ProposalPayload::Upgrade(code_hash),
+            ).unwrap();
+            assert_eq!(
+                contract.get_proposal(proposal_id).unwrap().payload,
+                ProposalPayload::Upgrade(code_hash)
+            );
 
-            // Test execution delays
-            assert_eq!(ExecutionDelay::Immediately.to_blocks(), 0);
-            assert_eq!(ExecutionDelay::OneDay.to_blocks(), 14_400);
-            assert_eq!(ExecutionDelay::TwoDays.to_blocks(), 28_800);
-            assert_eq!(ExecutionDelay::SevenDays.to_blocks(), 100_800);
+            // Current storage needs no migration
+            assert_eq!(contract.get_storage_version(), STORAGE_VERSION);
+            contract.migrate().unwrap();
+            assert_eq!(contract.get_storage_version(), STORAGE_VERSION);
+
+            // Storage written by older code is brought up to date
+            contract.storage_version = 0;
+            contract.migrate().unwrap();
+            assert_eq!(contract.get_storage_version(), STORAGE_VERSION);
         }
 
         #[ink::test]
-        fn test_8_quorum_calculation() {
+        fn test_39_role_based_access() {
+            let accounts = ink::env::test::default_accounts();
+            let alice = account_id(accounts.alice);
+            let bob = account_id(accounts.bob);
+            let charlie = account_id(accounts.charlie);
             let mut contract = TreasuryGovernance::new();
-            contract.total_voters = 10; // Simulate 10 registered voters
+
+            // The owner is implicitly an admin, registration implies membership
+            assert!(contract.has_role(Role::Admin, alice));
+            assert!(!contract.has_role(Role::Member, bob));
+            ink::env::test::set_caller(accounts.bob);
+            contract.register_voter();
+            assert!(contract.has_role(Role::Member, bob));
+
+            // Only admins manage roles and configuration
+            assert_eq!(contract.grant_role(Role::Admin, bob), Err(Error::NotAuthorized));
+            assert_eq!(contract.set_membership_policy(MembershipPolicy::RegisteredOnly), Err(Error::NotAuthorized));
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.grant_role(Role::Admin, bob).unwrap();
+            contract.grant_role(Role::Guardian, charlie).unwrap();
+            assert!(contract.has_role(Role::Admin, bob));
+            assert!(contract.has_role(Role::Guardian, charlie));
+
+            ink::env::test::set_caller(accounts.bob);
+            contract.set_membership_policy(MembershipPolicy::RegisteredOnly).unwrap();
 
             let proposal_id = contract.create_proposal(
-                String::from("Test Proposal"),
-                String::from("Test"),
-                ProposalType::Treasury,
-                default_governance_params(), // 10% quorum
+                String::from("Guarded"),
+                String::from("Cancelled by a granted guardian"),
+                ProposalType::Other,
+                default_governance_params(),
                 default_voting_options(),
             ).unwrap();
 
-            // With 10 total voters and 10% quorum, need 1 vote minimum
-            assert_eq!(contract.has_reached_quorum(proposal_id), false);
+            // Revoked roles no longer authorize
+            ink::env::test::set_caller(accounts.alice);
+            contract.revoke_role(Role::Admin, bob).unwrap();
+            assert!(!contract.has_role(Role::Admin, bob));
+            contract.revoke_role(Role::Guardian, charlie).unwrap();
+
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(contract.cancel_proposal(proposal_id), Err(Error::NotAuthorized));
+            ink::env::test::set_caller(accounts.alice);
+            contract.grant_role(Role::Guardian, charlie).unwrap();
+            ink::env::test::set_caller(accounts.charlie);
+            contract.cancel_proposal(proposal_id).unwrap();
+
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.set_membership_policy(MembershipPolicy::Open), Err(Error::NotAuthorized));
+
+            // Membership follows registration alone
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(contract.grant_role(Role::Member, charlie), Err(Error::InvalidRole));
+            assert_eq!(contract.revoke_role(Role::Member, bob), Err(Error::InvalidRole));
+            assert!(!contract.has_role(Role::Member, charlie));
+
+            // Proposers may propose without registering
+            let propose = |contract: &mut TreasuryGovernance| contract.create_proposal(
+                String::from("Outside Proposal"),
+                String::from("From an unregistered proposer"),
+                ProposalType::Other,
+                default_governance_params(),
+                default_voting_options(),
+            );
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(propose(&mut contract), Err(Error::NotRegistered));
+            ink::env::test::set_caller(accounts.alice);
+            contract.grant_role(Role::Proposer, charlie).unwrap();
+            ink::env::test::set_caller(accounts.charlie);
+            let proposal_id = propose(&mut contract).unwrap();
+            assert_eq!(contract.get_proposal(proposal_id).unwrap().proposer, charlie);
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::NotRegistered));
+
+            // Implied roles cannot be revoked
+            ink::env::test::set_caller(accounts.alice);
+            contract.revoke_role(Role::Admin, alice).unwrap();
+            assert!(contract.has_role(Role::Admin, alice));
+        }
 
-            contract.vote(proposal_id, 0).unwrap();
-            assert_eq!(contract.has_reached_quorum(proposal_id), true);
+        #[ink::test]
+        fn test_40_delegation_depth() {
+            let chain: Vec<H160> = (1u8..=7).map(|n| H160::from([n; 20])).collect();
+            let mut contract = TreasuryGovernance::new();
+
+            for caller in &chain {
+                ink::env::test::set_caller(*caller);
+                contract.register_voter();
+            }
+
+            // chain[5] -> chain[4] -> ... -> chain[0], the longest chain allowed
+            for pair in chain[..6].windows(2) {
+                ink::env::test::set_caller(pair[1]);
+                contract.delegate(account_id(pair[0])).unwrap();
+            }
+            assert_eq!(contract.resolve_delegate(account_id(chain[5])), account_id(chain[0]));
+
+            // Delegating the head would push the tail one level past the limit
+            ink::env::test::set_caller(chain[0]);
+            assert_eq!(contract.delegate(account_id(chain[6])), Err(Error::DelegationTooDeep));
+            assert_eq!(contract.get_delegation(account_id(chain[0])), None);
+
+            // A shorter subtree still fits
+            ink::env::test::set_caller(chain[1]);
+            contract.undelegate().unwrap();
+            ink::env::test::set_caller(chain[0]);
+            contract.delegate(account_id(chain[6])).unwrap();
+            assert_eq!(contract.resolve_delegate(account_id(chain[0])), account_id(chain[6]));
         }
 
         #[ink::test]
-        fn test_9_query_functions() {
+        fn test_41_delegation_subtree_limit() {
+            let accounts = ink::env::test::default_accounts();
+            let alice = account_id(accounts.alice);
+            let bob = account_id(accounts.bob);
             let mut contract = TreasuryGovernance::new();
+
+            let delegators: Vec<H160> = (0..=MAX_DELEGATION_SUBTREE as u64)
+                .map(|n| H160::from_low_u64_be(0x1000 + n))
+                .collect();
+            for caller in [accounts.alice, accounts.bob].iter().chain(&delegators) {
+                ink::env::test::set_caller(*caller);
+                contract.register_voter();
+            }
+
+            // Four direct delegates of alice, everyone else one level below them
+            let (direct, rest) = delegators[..MAX_DELEGATION_SUBTREE as usize].split_at(4);
+            for caller in direct {
+                ink::env::test::set_caller(*caller);
+                contract.delegate(alice).unwrap();
+            }
+            for (i, caller) in rest.iter().enumerate() {
+                ink::env::test::set_caller(*caller);
+                contract.delegate(account_id(direct[i % 4])).unwrap();
+            }
+            assert_eq!(contract.get_delegator_count(alice), MAX_DELEGATION_SUBTREE);
+            assert_eq!(contract.get_delegator_count(account_id(direct[0])), rest.len().div_ceil(4) as u32);
+
+            // A full subtree takes no one new at any level
+            let extra = delegators[MAX_DELEGATION_SUBTREE as usize];
+            ink::env::test::set_caller(extra);
+            assert_eq!(contract.delegate(alice), Err(Error::TooManyDelegators));
+            assert_eq!(contract.delegate(account_id(direct[1])), Err(Error::TooManyDelegators));
+            contract.delegate(bob).unwrap();
+            contract.undelegate().unwrap();
+
+            // Moving inside the subtree keeps its size
+            ink::env::test::set_caller(rest[0]);
+            contract.delegate(account_id(direct[1])).unwrap();
+            assert_eq!(contract.get_delegator_count(alice), MAX_DELEGATION_SUBTREE);
+
+            // A vote at the limit carries exactly the bounded subtree
             let proposal_id = contract.create_proposal(
-                String::from("Test Proposal"),
-                String::from("Test Description"),
-                ProposalType::Treasury,
+                String::from("Full Subtree"),
+                String::from("Alice votes for everyone below her"),
+                ProposalType::Other,
                 default_governance_params(),
                 default_voting_options(),
             ).unwrap();
-
+            ink::env::test::set_caller(accounts.alice);
             contract.vote(proposal_id, 0).unwrap();
+            let vote = contract.get_user_vote(proposal_id, alice).unwrap();
+            assert_eq!(vote.delegators.len(), MAX_DELEGATION_SUBTREE as usize);
+            assert_eq!(vote.weight, MAX_DELEGATION_SUBTREE as u128 + 1);
+
+            // Leaving frees room for someone else
+            ink::env::test::set_caller(direct[3]);
+            contract.undelegate().unwrap();
+            assert_eq!(
+                contract.get_delegator_count(alice),
+                MAX_DELEGATION_SUBTREE - 1 - contract.get_delegator_count(account_id(direct[3]))
+            );
+            ink::env::test::set_caller(extra);
+            contract.delegate(alice).unwrap();
+        }
 
-            // Test get_voting_options
-            let options = contract.get_voting_options(proposal_id).unwrap();
-            assert_eq!(options.len(), 3);
-            assert_eq!(options[0], String::from("Approve"));
+        #[ink::test]
+        fn test_42_delegated_reveals() {
+            let accounts = ink::env::test::default_accounts();
+            let alice = account_id(accounts.alice);
+            let bob = account_id(accounts.bob);
+            let mut contract = TreasuryGovernance::new();
 
-            // Test get_detailed_results
-            let results = contract.get_detailed_results(proposal_id).unwrap();
-            assert_eq!(results[0], (String::from("Approve"), 1));
-            assert_eq!(results[1], (String::from("Reject"), 0));
+            let mut params = default_governance_params();
+            params.voting_method = VotingMethod::CommitReveal { reveal_period: VotingPeriod::ThreeDays };
+            let proposal_ids: Vec<u32> = (0..2)
+                .map(|_| contract.create_proposal(
+                    String::from("Secret Ballot"),
+                    String::from("Delegate after committing"),
+                    ProposalType::Other,
+                    params.clone(),
+                    default_voting_options(),
+                ).unwrap())
+                .collect();
+
+            let salt = [7u8; 32];
+            let commitment = |proposal_id: u32, voter: AccountId, option_index: u32| {
+                let mut commitment = [0u8; 32];
+                ink::env::hash_encoded::<Keccak256, _>(&(proposal_id, voter, option_index, salt), &mut commitment);
+                commitment
+            };
 
-            // Test get_winning_option
-            let (winner, votes) = contract.get_winning_option(proposal_id).unwrap();
-            assert_eq!(winner, String::from("Approve"));
-            assert_eq!(votes, 1);
+            // Both commit on both proposals, then bob delegates to alice
+            for (caller, voter, option_index) in [(accounts.alice, alice, 0), (accounts.bob, bob, 1)] {
+                ink::env::test::set_caller(caller);
+                contract.register_voter();
+                for &proposal_id in &proposal_ids {
+                    contract.commit_vote(proposal_id, commitment(proposal_id, voter, option_index)).unwrap();
+                }
+            }
+            contract.delegate(alice).unwrap();
+
+            let voting_end = contract.get_proposal(proposal_ids[0]).unwrap().voting_end;
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(voting_end + 1);
+
+            // Delegate reveals first on one proposal, the delegator first on the other
+            let orders = [
+                [(accounts.alice, 0), (accounts.bob, 1)],
+                [(accounts.bob, 1), (accounts.alice, 0)],
+            ];
+            for (&proposal_id, order) in proposal_ids.iter().zip(orders) {
+                for (caller, option_index) in order {
+                    ink::env::test::set_caller(caller);
+                    contract.reveal_vote(proposal_id, option_index, salt).unwrap();
+                }
 
-            // Test get_proposal_results
-            let (vote_counts, _) = contract.get_proposal_results(proposal_id).unwrap();
-            assert_eq!(vote_counts[0], 1);
+                // Bob's committed weight is his own to reveal, never added to alice's ballot
+                let proposal = contract.get_proposal(proposal_id).unwrap();
+                assert_eq!(proposal.vote_counts, vec![1, 1, 0]);
+                assert_eq!(proposal.turnout, 2);
+                assert_eq!(proposal.total_voters, 2);
+                assert!(contract.get_user_vote(proposal_id, alice).unwrap().delegators.is_empty());
+            }
         }
 
         #[ink::test]
-        fn test_10_multiple_proposals_management() {
+        fn test_43_open_membership_payloads() {
+            let accounts = ink::env::test::default_accounts();
             let mut contract = TreasuryGovernance::new();
-
-            let id1 = contract.create_proposal(
-                String::from("Proposal 1"),
-                String::from("First"),
+            let spend = ProposalPayload::Spend(TreasurySpend {
+                beneficiary: accounts.django,
+                amount: 100,
+            });
+            let create_spend = |contract: &mut TreasuryGovernance| contract.create_proposal_with_payload(
+                String::from("Free Money"),
+                String::from("Paid for by throwaway addresses"),
                 ProposalType::Treasury,
                 default_governance_params(),
-                default_voting_options(),
-            ).unwrap();
+                binary_voting_options(),
+                spend.clone(),
+            );
 
-            let id2 = contract.create_proposal(
-                String::from("Proposal 2"),
-                String::from("Second"),
-                ProposalType::Governance,
+            // Anyone votes under open membership without a token, so only signals may be proposed
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(create_spend(&mut contract), Err(Error::PayloadNotAllowed));
+            ink::env::test::set_caller(accounts.alice);
+            assert_eq!(create_spend(&mut contract), Err(Error::PayloadNotAllowed));
+            assert!(contract.create_proposal(
+                String::from("Signal"),
+                String::from("Still allowed"),
+                ProposalType::Other,
                 default_governance_params(),
                 default_voting_options(),
-            ).unwrap();
-
-            assert_eq!(id1, 1);
-            assert_eq!(id2, 2);
-
-            let all_ids = contract.get_all_proposal_ids();
-            assert_eq!(all_ids.len(), 2);
+            ).is_ok());
 
-            let (total, active, _) = contract.get_stats();
-            assert_eq!(total, 2);
-            assert_eq!(active, 2);
+            // Spends can be proposed once voting takes registration
+            contract.set_membership_policy(MembershipPolicy::RegisteredOnly).unwrap();
+            contract.register_voter();
+            ink::env::test::set_caller(accounts.bob);
+            contract.register_voter();
+            let proposal_id = create_spend(&mut contract).unwrap();
+
+            // Reopening membership stops the spend from collecting votes
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_membership_policy(MembershipPolicy::Open).unwrap();
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::PayloadNotAllowed));
+            ink::env::test::set_caller(accounts.bob);
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::PayloadNotAllowed));
+
+            ink::env::test::set_caller(accounts.alice);
+            contract.set_membership_policy(MembershipPolicy::RegisteredOnly).unwrap();
+            ink::env::test::set_caller(accounts.charlie);
+            assert_eq!(contract.vote(proposal_id, 0), Err(Error::NotRegistered));
+            ink::env::test::set_caller(accounts.bob);
+            assert!(contract.vote(proposal_id, 0).is_ok());
         }
 
         #[ink::test]
-        fn test_11_custom_voting_options() {
-            let mut contract = TreasuryGovernance::new();
+        fn test_44_type_policy_validation() {
+            let accounts = ink::env::test::default_accounts();
+            let mut contract = registered_contract();
+            let propose_policy = |contract: &mut TreasuryGovernance, proposal_type: ProposalType, policy: TypePolicy| {
+                contract.create_proposal_with_payload(
+                    String::from("New Policy"),
+                    String::from("Change who may propose"),
+                    ProposalType::Governance,
+                    default_governance_params(),
+                    binary_voting_options(),
+                    ProposalPayload::Configure(ConfigChange::SetTypePolicy(proposal_type, policy)),
+                )
+            };
 
-            let custom_options = VotingOptions {
-                options: vec![
-                    String::from("Strongly Agree"),
-                    String::from("Agree"),
-                    String::from("Neutral"),
-                    String::from("Disagree"),
-                    String::from("Strongly Disagree"),
-                ],
+            // Minimums must fit the configured bounds and limits
+            let bounds = contract.get_duration_bounds();
+            let too_slow = TypePolicy {
+                min_voting_period: bounds.max_voting_period + 1,
+                ..TypePolicy::default()
             };
+            assert_eq!(propose_policy(&mut contract, ProposalType::Treasury, too_slow), Err(Error::InvalidDuration));
+            let too_late = TypePolicy {
+                min_execution_delay: bounds.max_execution_delay + 1,
+                ..TypePolicy::default()
+            };
+            assert_eq!(propose_policy(&mut contract, ProposalType::Treasury, too_late), Err(Error::InvalidDuration));
+            contract.set_quorum_limits(QuorumLimits {
+                min_basis_points: 100,
+                max_basis_points: 5_000,
+                min_votes: 0,
+            }).unwrap();
+            let too_strict = TypePolicy {
+                min_quorum_basis_points: 5_001,
+                ..TypePolicy::default()
+            };
+            assert_eq!(propose_policy(&mut contract, ProposalType::Treasury, too_strict), Err(Error::InvalidQuorum));
 
-            let proposal_id = contract.create_proposal(
-                String::from("Custom Options Test"),
-                String::from("Testing 5-point scale"),
-                ProposalType::Governance,
-                default_governance_params(),
-                custom_options,
-            ).unwrap();
+            // Governance proposals must stay possible for some current role holder
+            let guardians_only = TypePolicy {
+                allowed_proposers: vec![Role::Guardian],
+                ..TypePolicy::default()
+            };
+            assert_eq!(
+                propose_policy(&mut contract, ProposalType::Governance, guardians_only.clone()),
+                Err(Error::InvalidProposal)
+            );
+            let proposers_only = TypePolicy {
+                allowed_proposers: vec![Role::Proposer],
+                ..TypePolicy::default()
+            };
+            assert_eq!(
+                propose_policy(&mut contract, ProposalType::Governance, proposers_only.clone()),
+                Err(Error::InvalidProposal)
+            );
+            contract.grant_role(Role::Proposer, account_id(accounts.bob)).unwrap();
+            assert!(propose_policy(&mut contract, ProposalType::Governance, proposers_only).is_ok());
+            contract.revoke_role(Role::Proposer, account_id(accounts.bob)).unwrap();
 
-            let options = contract.get_voting_options(proposal_id).unwrap();
-            assert_eq!(options.len(), 5);
-            assert_eq!(options[0], String::from("Strongly Agree"));
-            assert_eq!(options[4], String::from("Strongly Disagree"));
+            // Other types may be closed to roles nobody holds yet
+            assert!(propose_policy(&mut contract, ProposalType::Treasury, guardians_only.clone()).is_ok());
+
+            // The policy is checked again when executed
+            contract.set_guardian(Some(account_id(accounts.charlie))).unwrap();
+            let proposal_id = propose_policy(&mut contract, ProposalType::Governance, guardians_only).unwrap();
+            contract.vote(proposal_id, 0).unwrap();
+            contract.set_guardian(None).unwrap();
+
+            let proposal = contract.get_proposal(proposal_id).unwrap();
+            ink::env::test::set_block_number::<ink::env::DefaultEnvironment>(proposal.execution_time);
+            assert_eq!(contract.execute_proposal(proposal_id), Err(Error::InvalidProposal));
+            assert_eq!(contract.get_type_policy(ProposalType::Governance), TypePolicy::default());
         }
+    }
 
-        #[ink::test]
-        fn test_12_proposal_types_and_execution() {
-            let mut contract = TreasuryGovernance::new();
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use checkpoint_token::{CheckpointToken, CheckpointTokenRef};
+        use ink_e2e::ContractsBackend;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test]
+        async fn e2e_token_weights_use_snapshot<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+            let mut token_constructor = CheckpointTokenRef::new();
+            let token = client
+                .instantiate("checkpoint_token", &ink_e2e::alice(), &mut token_constructor)
+                .submit()
+                .await
+                .expect("token instantiate failed");
+            let mut token_calls = token.call_builder::<CheckpointToken>();
+
+            let mut constructor = TreasuryGovernanceRef::with_governance_token(MembershipPolicy::Open, token.addr);
+            let contract = client
+                .instantiate("treasury", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let mut call_builder = contract.call_builder::<TreasuryGovernance>();
+            let governance_token = client
+                .call(&ink_e2e::alice(), &call_builder.get_governance_token())
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(governance_token, Some(token.addr));
+
+            // Bob holds every token when the proposal is created
+            client
+                .call(&ink_e2e::bob(), &token_calls.mint(100))
+                .submit()
+                .await
+                .expect("mint failed");
+
+            let create = call_builder.create_proposal(
+                String::from("Weighted"),
+                String::from("Token-weighted vote"),
+                ProposalType::Other,
+                GovernanceParameters {
+                    voting_period: VotingPeriod::SevenDays,
+                    quorum_threshold: QuorumThreshold::Ten,
+                    execution_delay: ExecutionDelay::OneDay,
+                    voting_method: VotingMethod::SingleChoice,
+                    approval_threshold: ApprovalThreshold::SimpleMajority,
+                },
+                VotingOptions {
+                    options: vec![String::from("Yes"), String::from("No")],
+                    roles: vec![],
+                },
+            );
+            let proposal_id = client
+                .call(&ink_e2e::alice(), &create)
+                .submit()
+                .await
+                .expect("create_proposal failed")
+                .return_value()
+                .expect("proposal rejected");
+
+            // Bob then hands all of them to alice
+            let alice = client
+                .call(&ink_e2e::alice(), &call_builder.get_owner())
+                .dry_run()
+                .await?
+                .return_value();
+            client
+                .call(&ink_e2e::bob(), &token_calls.transfer(TreasuryGovernance::account_address(alice), 100))
+                .submit()
+                .await
+                .expect("transfer failed")
+                .return_value()
+                .expect("transfer rejected");
+
+            // Bob votes with his snapshot balance, the moved tokens cannot vote again
+            client
+                .call(&ink_e2e::bob(), &call_builder.vote(proposal_id, 0))
+                .submit()
+                .await
+                .expect("vote failed")
+                .return_value()
+                .expect("vote rejected");
+            let alice_vote = client
+                .call(&ink_e2e::alice(), &call_builder.vote(proposal_id, 1))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(alice_vote, Err(Error::NoVotingPower));
+
+            let proposal = client
+                .call(&ink_e2e::alice(), &call_builder.get_proposal(proposal_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .expect("proposal lost");
+            assert_eq!(proposal.total_voting_power, 100);
+            assert_eq!(proposal.vote_counts, vec![100, 0]);
+            assert_eq!(proposal.turnout, 100);
 
-            // Test different proposal types
-            let treasury_id = contract.create_proposal(
-                String::from("Treasury Proposal"),
-                String::from("Fund allocation"),
-                ProposalType::Treasury,
-                default_governance_params(),
-                default_voting_options(),
-            ).unwrap();
+            Ok(())
+        }
 
-            let governance_id = contract.create_proposal(
-                String::from("Governance Change"),
-                String::from("Protocol update"),
-                ProposalType::Governance,
-                default_governance_params(),
-                default_voting_options(),
-            ).unwrap();
+        #[ink_e2e::test]
+        async fn e2e_token_without_checkpoints_rejected<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+            // Any contract without `PSP22Checkpoints` fails the probe, here another treasury
+            let mut other_constructor = TreasuryGovernanceRef::new();
+            let other = client
+                .instantiate("treasury", &ink_e2e::alice(), &mut other_constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+
+            let mut constructor = TreasuryGovernanceRef::with_governance_token(MembershipPolicy::Open, other.addr);
+            let result = client
+                .instantiate("treasury", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await;
+            assert!(result.is_err());
 
-            let technical_id = contract.create_proposal(
-                String::from("Technical Upgrade"),
-                String::from("System enhancement"),
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn e2e_upgrade_preserves_state<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+            let mut constructor = TreasuryGovernanceRef::with_membership_policy(MembershipPolicy::RegisteredOnly);
+            let contract = client
+                .instantiate("treasury", &ink_e2e::alice(), &mut constructor)
+                .submit()
+                .await
+                .expect("instantiate failed");
+            let mut call_builder = contract.call_builder::<TreasuryGovernance>();
+
+            // Allow a proposal that can be decided within a few blocks
+            let bounds = DurationBounds {
+                min_voting_period: 1,
+                max_voting_period: 100,
+                min_execution_delay: 0,
+                max_execution_delay: 100,
+            };
+            client
+                .call(&ink_e2e::alice(), &call_builder.set_duration_bounds(bounds))
+                .submit()
+                .await
+                .expect("set_duration_bounds failed");
+            client
+                .call(&ink_e2e::alice(), &call_builder.register_voter())
+                .submit()
+                .await
+                .expect("register_voter failed");
+
+            // Upgrade to code with the next storage version
+            let code_hash = client
+                .upload("treasury_v2", &ink_e2e::alice())
+                .submit()
+                .await
+                .expect("upload failed")
+                .code_hash;
+
+            let params = GovernanceParameters {
+                voting_period: VotingPeriod::Custom(2),
+                quorum_threshold: QuorumThreshold::Ten,
+                execution_delay: ExecutionDelay::Immediately,
+                voting_method: VotingMethod::SingleChoice,
+                approval_threshold: ApprovalThreshold::SimpleMajority,
+            };
+            let options = VotingOptions {
+                options: vec![String::from("Upgrade"), String::from("Keep")],
+                roles: vec![OptionRole::For, OptionRole::Against],
+            };
+            let create = call_builder.create_proposal_with_payload(
+                String::from("Upgrade"),
+                String::from("Replace the contract code"),
                 ProposalType::Technical,
-                default_governance_params(),
-                default_voting_options(),
-            ).unwrap();
+                params,
+                options,
+                ProposalPayload::Upgrade(code_hash),
+            );
+            let proposal_id = client
+                .call(&ink_e2e::alice(), &create)
+                .submit()
+                .await
+                .expect("create_proposal_with_payload failed")
+                .return_value()
+                .expect("proposal rejected");
+
+            client
+                .call(&ink_e2e::alice(), &call_builder.vote(proposal_id, 0))
+                .submit()
+                .await
+                .expect("vote failed");
+
+            // Let the voting period run out
+            for _ in 0..3 {
+                client
+                    .call(&ink_e2e::alice(), &call_builder.register_voter())
+                    .submit()
+                    .await
+                    .expect("register_voter failed");
+            }
 
-            assert_eq!(contract.get_proposal(treasury_id).unwrap().proposal_type, ProposalType::Treasury);
-            assert_eq!(contract.get_proposal(governance_id).unwrap().proposal_type, ProposalType::Governance);
-            assert_eq!(contract.get_proposal(technical_id).unwrap().proposal_type, ProposalType::Technical);
+            client
+                .call(&ink_e2e::alice(), &call_builder.execute_proposal(proposal_id))
+                .submit()
+                .await
+                .expect("execute_proposal failed")
+                .return_value()
+                .expect("upgrade failed");
+
+            // The new code decodes the old root and still reports the old version
+            let mut upgraded = contract.call_builder::<treasury_v2::TreasuryGovernance>();
+            let storage_version = client
+                .call(&ink_e2e::alice(), &upgraded.get_storage_version())
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(storage_version, STORAGE_VERSION);
+
+            // Migrating rewrites the duration bounds in their new layout
+            client
+                .call(&ink_e2e::alice(), &upgraded.migrate())
+                .submit()
+                .await
+                .expect("migrate failed");
+            let storage_version = client
+                .call(&ink_e2e::alice(), &upgraded.get_storage_version())
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(storage_version, 2);
+
+            let duration_bounds = client
+                .call(&ink_e2e::alice(), &upgraded.get_duration_bounds())
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(duration_bounds.min_voting_period, 1);
+            assert_eq!(duration_bounds.max_voting_period, 100);
+            assert_eq!(duration_bounds.max_execution_delay, 100);
+            assert_eq!(duration_bounds.max_reveal_period, 100);
+
+            // Proposals and voters survive the upgrade
+            let proposal_ids = client
+                .call(&ink_e2e::alice(), &upgraded.get_all_proposal_ids())
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(proposal_ids, vec![proposal_id]);
+            let total_voters = client
+                .call(&ink_e2e::alice(), &upgraded.get_total_voters())
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(total_voters, 1);
+
+            // The new code decodes the stored proposal and alice's vote
+            let alice = client
+                .call(&ink_e2e::alice(), &call_builder.get_owner())
+                .dry_run()
+                .await?
+                .return_value();
+            let proposal = client
+                .call(&ink_e2e::alice(), &upgraded.get_proposal(proposal_id))
+                .dry_run()
+                .await?
+                .return_value()
+                .expect("proposal lost");
+            assert_eq!(proposal.id, proposal_id);
+            assert_eq!(proposal.status, treasury_v2::ProposalStatus::Executed);
+            assert_eq!(proposal.vote_counts, vec![1, 0]);
+            assert_eq!(proposal.turnout, 1);
+            let vote = client
+                .call(&ink_e2e::alice(), &upgraded.get_user_vote(proposal_id, alice))
+                .dry_run()
+                .await?
+                .return_value()
+                .expect("vote lost");
+            assert_eq!(vote.voter, alice);
+            assert_eq!(vote.choices.len(), 1);
+            assert_eq!(vote.choices[0].option_index, 0);
+            assert_eq!(vote.weight, 1);
 
-            // Test execution validation
-            let result = contract.execute_proposal(treasury_id);
-            assert_eq!(result, Err(Error::ProposalNotReadyForExecution));
+            Ok(())
         }
     }
 }